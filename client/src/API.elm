@@ -4,7 +4,7 @@ import Array exposing (Array)
 import Coverage exposing (..)
 import Http
 import Json.Decode as D
-import MainModel exposing (CoverageKindFilter, InputFilter, InputInfo, Model, getSelectedPoolIdx)
+import MainModel exposing (CoverageKindFilter, InputFilter, InputInfo, Model, TargetInfo, getSelectedPoolIdx)
 import Url.Builder as UrlB
 
 
@@ -43,6 +43,35 @@ getCoverageCmd getmsg model =
             Cmd.none
 
 
+getFileCoverageUrl : Model -> Maybe String
+getFileCoverageUrl model =
+    Maybe.map2
+        (\input_filter_string ( file, _ ) ->
+            UrlB.relative [ "coverage", "file" ]
+                [ UrlB.string "input_filter" input_filter_string
+                , UrlB.string "file" file
+                , UrlB.string "coverage_kind_filter" (getCoverageKindFilterString model.coverage_kind_filter)
+                ]
+        )
+        (getInputFilterString model)
+        (model.selected_file |> Maybe.andThen (\i -> Array.get i model.all_files))
+
+
+{-| Fetches the coverage of every line of the selected file
+-}
+getFileCoverageCmd : (Result Http.Error FunctionCoverage -> msg) -> Model -> Cmd msg
+getFileCoverageCmd getmsg model =
+    case getFileCoverageUrl model of
+        Just url ->
+            Http.get
+                { url = url
+                , expect = Http.expectJson getmsg decodeFileCoverage
+                }
+
+        Nothing ->
+            Cmd.none
+
+
 getListOfFunctionsUrl : Model -> Maybe String
 getListOfFunctionsUrl model =
     Maybe.map
@@ -119,6 +148,26 @@ getListOfInputsCmd getmsg =
         }
 
 
+getListOfTargetsCmd : (Result Http.Error (Array TargetInfo) -> msg) -> Cmd msg
+getListOfTargetsCmd getmsg =
+    Http.get
+        { url = "targets"
+        , expect = Http.expectJson getmsg (D.array MainModel.decodeTargetInfo)
+        }
+
+
+{-| Makes the fuzz test `name` the one whose data is returned by the other
+requests
+-}
+selectTargetCmd : (Result Http.Error TargetInfo -> msg) -> String -> Cmd msg
+selectTargetCmd getmsg name =
+    Http.post
+        { url = UrlB.relative [ "target" ] [ UrlB.string "target" name ]
+        , body = Http.emptyBody
+        , expect = Http.expectJson getmsg MainModel.decodeTargetInfo
+        }
+
+
 getInputFilterString : { a | input_filter : InputFilter, all_inputs : Array { b | pool_idx : Int }, selected_input : Maybe Int } -> Maybe String
 getInputFilterString model =
     case model.input_filter of
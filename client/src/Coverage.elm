@@ -33,6 +33,7 @@ type alias CodeLine =
 type alias CodeSpan =
     { text : String
     , kind : CodeSpanKind
+    , token : Maybe TokenClass
     }
 
 
@@ -50,6 +51,16 @@ type CoverageStatus
     | Unknown
 
 
+{-| The Rust tokens whose text is colored
+-}
+type TokenClass
+    = KeywordToken
+    | StringToken
+    | CommentToken
+    | MacroToken
+    | LifetimeToken
+
+
 type Msg
     = UnselectCounter
     | SelectCounter Int
@@ -142,11 +153,11 @@ viewCodeSpan { block, layout, focused_id, span } =
     case span.kind of
         Untracked ->
             E.el
-                [ Font.color fg ]
+                (Font.color (tokenColor span.token fg) :: tokenStyle span.token)
                 (E.text span.text)
 
         Tracked id status ->
-            viewTrackedCodeSpan { block = block, layout = layout, focused_id = focused_id, id = id, status = status, text = span.text }
+            viewTrackedCodeSpan { block = block, layout = layout, focused_id = focused_id, id = id, status = status, text = span.text, token = span.token }
 
         Inferred status ->
             let
@@ -155,13 +166,13 @@ viewCodeSpan { block, layout, focused_id, span } =
                         color =
                             coverageStatusColor status
                     in
-                    ( color, bgCode )
+                    ( tokenColor span.token color, bgCode )
             in
-            E.el [ Font.color fgColor, Background.color bgColor ]
+            E.el (Font.color fgColor :: Background.color bgColor :: tokenStyle span.token)
                 (E.text span.text)
 
 
-viewTrackedCodeSpan : CodeBlockData { a | id : Int, status : CoverageStatus, text : String, focused_id : Maybe Int } -> E.Element Msg
+viewTrackedCodeSpan : CodeBlockData { a | id : Int, status : CoverageStatus, text : String, token : Maybe TokenClass, focused_id : Maybe Int } -> E.Element Msg
 viewTrackedCodeSpan model =
     let
         ( fgColor, bgColor ) =
@@ -173,15 +184,17 @@ viewTrackedCodeSpan model =
                 ( bgCode, color )
 
             else
-                ( color, makeTransparent color 0.2 )
+                ( tokenColor model.token color, makeTransparent color 0.2 )
     in
     E.el
-        [ E.pointer
-        , Font.color fgColor
-        , Background.color bgColor
-        , EE.onMouseEnter (SelectCounter model.id)
-        , EE.onMouseLeave UnselectCounter
-        ]
+        ([ E.pointer
+         , Font.color fgColor
+         , Background.color bgColor
+         , EE.onMouseEnter (SelectCounter model.id)
+         , EE.onMouseLeave UnselectCounter
+         ]
+            ++ tokenStyle model.token
+        )
         (E.text model.text)
 
 
@@ -205,6 +218,43 @@ coverageStatusColor status =
 
 
 
+{-| The color of the text of a token, or `default` if the text is not part of
+a colored token. As in the static HTML reports, the coverage is then only
+shown by the background of tracked spans.
+-}
+tokenColor : Maybe TokenClass -> E.Color -> E.Color
+tokenColor token default =
+    case token of
+        Just KeywordToken ->
+            orange
+
+        Just StringToken ->
+            lime
+
+        Just CommentToken ->
+            lightBlue
+
+        Just MacroToken ->
+            salmon
+
+        Just LifetimeToken ->
+            purple
+
+        Nothing ->
+            default
+
+
+tokenStyle : Maybe TokenClass -> List (E.Attribute msg)
+tokenStyle token =
+    case token of
+        Just CommentToken ->
+            [ Font.italic ]
+
+        _ ->
+            []
+
+
+
 -- isCodeSpanKindFocused : CodeSpanKind -> Maybe Int -> Bool
 -- isCodeSpanKindFocused kind focused_id =
 --     case kind of
@@ -272,11 +322,37 @@ decodeCoverageStatus =
             )
 
 
+decodeTokenClass : D.Decoder TokenClass
+decodeTokenClass =
+    D.string
+        |> D.andThen
+            (\value ->
+                case value of
+                    "Keyword" ->
+                        D.succeed KeywordToken
+
+                    "String" ->
+                        D.succeed StringToken
+
+                    "Comment" ->
+                        D.succeed CommentToken
+
+                    "Macro" ->
+                        D.succeed MacroToken
+
+                    "Lifetime" ->
+                        D.succeed LifetimeToken
+
+                    _ ->
+                        D.fail <| "failed to decode TokenClass"
+            )
+
+
 {-| Decodes a CodeSpan
 -}
 decodeCodeSpan : D.Decoder CodeSpan
 decodeCodeSpan =
-    D.map2 CodeSpan
+    D.map3 CodeSpan
         (D.field
             "text"
             D.string
@@ -285,6 +361,7 @@ decodeCodeSpan =
             "kind"
             decodeCodeSpanKind
         )
+        (D.maybe (D.field "token" decodeTokenClass))
 
 
 {-| Decodes a FunctionName
@@ -310,3 +387,24 @@ decodeFunctionCoverage =
         (D.field "file" D.string)
         (D.field "lines" (D.list decodeCodeLine))
         (D.field "counter_ids" (D.list D.int))
+
+
+{-| Decodes a FileCoverage, shown as a single block made of every line of the
+file
+-}
+decodeFileCoverage : D.Decoder FunctionCoverage
+decodeFileCoverage =
+    D.map4 FunctionCoverage
+        (D.map
+            (\functions ->
+                if List.length functions == 1 then
+                    "1 function"
+
+                else
+                    String.fromInt (List.length functions) ++ " functions"
+            )
+            (D.field "functions" (D.list (D.succeed ())))
+        )
+        (D.field "file" D.string)
+        (D.field "lines" (D.list decodeCodeLine))
+        (D.field "counter_ids" (D.list D.int))
@@ -1,5 +1,7 @@
 module Helpers exposing (..)
 
+import Array exposing (Array)
+
 
 prevOptInt : Maybe Int -> Int
 prevOptInt x =
@@ -45,3 +47,22 @@ nextInt max y =
 
     else
         y
+
+
+findIndex : (a -> Bool) -> Array a -> Maybe Int
+findIndex predicate array =
+    findIndexFrom 0 predicate array
+
+
+findIndexFrom : Int -> (a -> Bool) -> Array a -> Maybe Int
+findIndexFrom i predicate array =
+    case Array.get i array of
+        Nothing ->
+            Nothing
+
+        Just item ->
+            if predicate item then
+                Just i
+
+            else
+                findIndexFrom (i + 1) predicate array
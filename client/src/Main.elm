@@ -1,4 +1,4 @@
-module Main exposing (main)
+port module Main exposing (main)
 
 -- import Element.Events as Events
 
@@ -49,6 +49,7 @@ init _ =
         [ Task.perform (\vp -> Resize (round vp.viewport.width) (round vp.viewport.height)) Browser.Dom.getViewport
         , API.getFilesAndFunctionsCmd GotFunctions model
         , API.getListOfInputsCmd GotInputs
+        , API.getListOfTargetsCmd GotTargets
         ]
     )
 
@@ -57,9 +58,15 @@ init _ =
 -- Subscriptions
 
 
+{-| Notified by the page whenever the server reloaded the coverage of the
+fuzz test shown (see --watch)
+-}
+port reloaded : (() -> msg) -> Sub msg
+
+
 subscriptions : Model -> Sub Msg
 subscriptions _ =
-    Sub.batch [ Browser.Events.onKeyDown keyDecoder, Browser.Events.onResize Resize ]
+    Sub.batch [ Browser.Events.onKeyDown keyDecoder, Browser.Events.onResize Resize, reloaded (\_ -> Reloaded) ]
 
 
 keyDecoder : D.Decoder Msg
@@ -120,6 +127,12 @@ type Msg
     | SelectInput ListSelect.Msg
     | ChangeCoverageKindFilter CoverageKindFilter
     | ChangeFunctionFilter FunctionFilter
+    | Reloaded
+    | GotReloadedInputs (Result Http.Error (Array InputInfo))
+    | GotReloadedFunctions (Result Http.Error (Array ( String, Array FunctionName )))
+    | GotTargets (Result Http.Error (Array TargetInfo))
+    | SelectTarget ListSelect.Msg
+    | GotSelectedTarget (Result Http.Error TargetInfo)
 
 
 update : Msg -> Model -> ( Model, Cmd Msg )
@@ -162,11 +175,12 @@ update msg model =
                             ListSelect.update m (MainModel.fileSelectModel model)
                     in
                     let
+                        -- the whole file is shown until one of its functions is selected
                         newModel =
-                            { model | selected_file = files.selected_item, selected_function = Nothing, function_coverage = Nothing }
+                            { model | selected_file = files.selected_item, selected_function = Nothing, cached_selected_function = Nothing, function_coverage = Nothing }
                     in
                     ( newModel
-                    , API.getCoverageCmd GotCodeBlock newModel
+                    , API.getFileCoverageCmd GotCodeBlock newModel
                     )
 
         PreviousFunction ->
@@ -201,7 +215,9 @@ update msg model =
                     ( model, Cmd.none )
 
                 UnSelect ->
-                    ( { model | selected_function = Nothing, function_coverage = Nothing }, Cmd.none )
+                    ( { model | selected_function = Nothing, cached_selected_function = Nothing, function_coverage = Nothing }
+                    , API.getFileCoverageCmd GotCodeBlock model
+                    )
 
                 Select _ ->
                     let
@@ -367,6 +383,101 @@ update msg model =
             in
             ( newModel, API.getFilesAndFunctionsCmd GotFunctions newModel )
 
+        Reloaded ->
+            ( model, API.getListOfInputsCmd GotReloadedInputs )
+
+        GotReloadedInputs (Ok inputs) ->
+            let
+                -- the pool indices may have changed, so the input is found again by its hash
+                selected_hash =
+                    model.selected_input
+                        |> Maybe.andThen (\i -> Array.get i model.all_inputs)
+                        |> Maybe.map .hash
+
+                newModel =
+                    { model
+                        | all_inputs = inputs
+                        , selected_input =
+                            selected_hash
+                                |> Maybe.andThen (\hash -> Helpers.findIndex (\input -> input.hash == hash) inputs)
+                    }
+            in
+            ( newModel, API.getFilesAndFunctionsCmd GotReloadedFunctions newModel )
+
+        GotReloadedInputs (Err _) ->
+            ( model, API.getFilesAndFunctionsCmd GotReloadedFunctions model )
+
+        GotReloadedFunctions (Ok functions) ->
+            let
+                selected_file =
+                    model.selected_file
+                        |> Maybe.andThen (\i -> Array.get i model.all_files)
+                        |> Maybe.andThen (\( name, _ ) -> Helpers.findIndex (\( file, _ ) -> file == name) functions)
+
+                selected_function =
+                    case ( selected_file, model.selected_function, model.cached_selected_function ) of
+                        ( Just file, Just _, Just cached ) ->
+                            Array.get file functions
+                                |> Maybe.andThen (\( _, file_functions ) -> Helpers.findIndex (\f -> f.name == cached.name) file_functions)
+
+                        _ ->
+                            Nothing
+
+                newModel =
+                    { model | all_files = functions, selected_file = selected_file, selected_function = selected_function }
+            in
+            case selected_function of
+                Nothing ->
+                    ( { newModel | function_coverage = Nothing }, API.getFileCoverageCmd GotCodeBlock newModel )
+
+                Just _ ->
+                    ( newModel, API.getCoverageCmd GotCodeBlock newModel )
+
+        GotReloadedFunctions (Err _) ->
+            ( model, Cmd.none )
+
+        GotTargets (Ok targets) ->
+            ( { model | all_targets = targets, selected_target = Helpers.findIndex .selected targets }, Cmd.none )
+
+        GotTargets (Err _) ->
+            ( { model | all_targets = Array.empty, selected_target = Nothing }, Cmd.none )
+
+        SelectTarget (ListSelect.Select idx) ->
+            case Array.get idx model.all_targets of
+                Just target ->
+                    ( model, API.selectTargetCmd GotSelectedTarget target.name )
+
+                Nothing ->
+                    ( model, Cmd.none )
+
+        SelectTarget _ ->
+            ( model, Cmd.none )
+
+        GotSelectedTarget (Ok target) ->
+            let
+                -- the inputs and the functions of the other fuzz test are fetched as on startup
+                newModel =
+                    { emptyModel
+                        | layout = model.layout
+                        , function_filter = model.function_filter
+                        , coverage_kind_filter = model.coverage_kind_filter
+                        , all_targets =
+                            Array.map
+                                (\t -> { t | selected = t.name == target.name, loaded = t.loaded || t.name == target.name })
+                                model.all_targets
+                        , selected_target = Helpers.findIndex (\t -> t.name == target.name) model.all_targets
+                    }
+            in
+            ( newModel
+            , Cmd.batch
+                [ API.getFilesAndFunctionsCmd GotFunctions newModel
+                , API.getListOfInputsCmd GotInputs
+                ]
+            )
+
+        GotSelectedTarget (Err _) ->
+            ( { model | error = Just "the fuzz test could not be loaded" }, Cmd.none )
+
 
 
 -- VIEW
@@ -388,6 +499,22 @@ mainView model =
 
             Nothing ->
                 E.none
+        , if Array.length model.all_targets > 1 then
+            E.row [ E.alignTop, E.width E.fill, E.spacing model.layout.column_sep ]
+                [ E.column [ E.spacing normalSpacing, E.alignTop, E.width (E.px model.layout.column_width) ]
+                    [ E.row [ E.padding normalSpacing, E.width E.fill, Background.color fg, Font.family codeFontFamily, Font.color bgCode, Font.size largeFontSize ]
+                        [ E.el [ E.alignLeft ] (E.text "Fuzz tests") ]
+                    , E.el [ E.height (E.shrink |> E.maximum 140), E.width (E.px model.layout.column_width) ]
+                        (E.map SelectTarget
+                            (ListSelect.view
+                                { all_items = Array.map .name model.all_targets, selected_item = model.selected_target }
+                            )
+                        )
+                    ]
+                ]
+
+          else
+            E.none
         , E.row [ E.alignTop, E.width E.fill, E.spacing model.layout.column_sep ]
             [ E.column [ E.spacing normalSpacing, E.alignTop, E.width (E.px model.layout.column_width) ]
                 [ E.row [ E.padding normalSpacing, E.width E.fill, Background.color fg, Font.family codeFontFamily, Font.color bgCode, Font.size largeFontSize ]
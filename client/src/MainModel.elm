@@ -26,6 +26,8 @@ type alias Model =
     , all_inputs : Array InputInfo
     , selected_input : Maybe Int
     , previewed_input : Maybe ( String, String )
+    , all_targets : Array TargetInfo
+    , selected_target : Maybe Int
     , error : Maybe String
     }
 
@@ -46,6 +48,8 @@ emptyModel =
     , all_inputs = Array.empty
     , selected_input = Nothing
     , previewed_input = Nothing
+    , all_targets = Array.empty
+    , selected_target = Nothing
     , error = Nothing
     }
 
@@ -72,6 +76,15 @@ type alias InputInfo =
     }
 
 
+{-| A fuzz test of the crate, `selected` if it is the one shown
+-}
+type alias TargetInfo =
+    { name : String
+    , loaded : Bool
+    , selected : Bool
+    }
+
+
 getSelectedPoolIdx : { a | all_inputs : Array { b | pool_idx : Int }, input_filter : InputFilter, selected_input : Maybe Int } -> Maybe Int
 getSelectedPoolIdx model =
     Maybe.andThen (\selected_input -> Maybe.map .pool_idx (Array.get selected_input model.all_inputs)) model.selected_input
@@ -82,6 +95,11 @@ decodeInputInfo =
     D.map2 InputInfo (D.field "pool_idx" D.int) (D.field "hash" D.string)
 
 
+decodeTargetInfo : D.Decoder TargetInfo
+decodeTargetInfo =
+    D.map3 TargetInfo (D.field "name" D.string) (D.field "loaded" D.bool) (D.field "selected" D.bool)
+
+
 fileSelectModel : { a | all_files : Array ( String, Array FunctionName ), selected_file : Maybe Int } -> ListSelect.Model
 fileSelectModel model =
     ListSelect.Model (Array.map Tuple.first model.all_files) model.selected_file
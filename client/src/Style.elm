@@ -55,6 +55,26 @@ green =
     E.rgb255 0x87 0xD9 0x6C
 
 
+orange : Color
+orange =
+    E.rgb255 0xFF 0xAD 0x66
+
+
+lime : Color
+lime =
+    E.rgb255 0xD5 0xFF 0x80
+
+
+lightBlue : Color
+lightBlue =
+    E.rgb255 0xB8 0xCF 0xE6
+
+
+salmon : Color
+salmon =
+    E.rgb255 0xF2 0x87 0x79
+
+
 actionColor : Color
 actionColor =
     E.rgb255 0x69 0x53 0x80
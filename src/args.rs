@@ -1,28 +1,180 @@
-use std::{path::PathBuf, process::exit};
+use std::{net::IpAddr, path::PathBuf, process::exit};
 
 use getopts::Options;
 
+use crate::data::RunSelector;
+use crate::decode::InputDecoder;
+use crate::discover::{fuzz_test, Workspace};
+use crate::error::Error;
+use crate::report::Requirements;
+use crate::sources::SourceOrigin;
+
+const USAGE: &str = "fuzzcheck-view [--watch] [--address ADDRESS] [--port PORT] [--base-path PATH] [serve | tui | export [--html DIRECTORY] [--lcov FILE] [--cobertura FILE] | summary [--json] [--min-coverage PERCENT] [--require-hit FUNCTION]... | patch [RANGE] [--json] [--min-coverage PERCENT] | snapshot] [--source-revision REVISION | --source-snapshot] [-d DIRECTORY] [-t FUZZ_TARGET] [-w WORKSPACE]";
+
+#[derive(Clone)]
 pub struct CliArguments {
     pub workspace_directory: PathBuf,
     pub crate_directory: PathBuf,
     pub test: String,
+    pub run: RunSelector,
+    /// where the source files of the instrumented code are read from
+    pub sources: SourceOrigin,
+    /// default decoder of the inputs of the corpus
+    pub decoder: InputDecoder,
+    /// show the instantiations of generic functions as a single function
+    pub merge_generics: bool,
+    /// reload the data whenever fuzzcheck modifies it
+    pub watch: bool,
+    /// address the server listens on, Rocket's default if not given
+    pub address: Option<IpAddr>,
+    /// port the server listens on, Rocket's default if not given
+    pub port: Option<u16>,
+    /// URL prefix of the viewer, starting with a slash and without a trailing one
+    pub base_path: String,
+    pub command: Command,
+}
+
+#[derive(Clone)]
+pub enum Command {
+    /// serve the coverage viewer on localhost
+    Serve,
+    /// show the coverage in the terminal
+    Tui,
+    /// print the coverage and check that it meets the requirements
+    Summary { json: bool, requirements: Requirements },
+    /// print the coverage of the lines changed by `git diff <range>`
+    Patch {
+        range: String,
+        json: bool,
+        min_coverage: Option<f64>,
+    },
+    /// copy the source files into the stats folder of the run
+    Snapshot,
+    /// write a coverage report to disk
+    Export {
+        html: Option<PathBuf>,
+        lcov: Option<PathBuf>,
+        cobertura: Option<PathBuf>,
+    },
+}
+
+impl CliArguments {
+    /// Returns the arguments selecting the most recent run of another fuzz
+    /// test of the same crate.
+    pub fn with_test(&self, test: &str) -> CliArguments {
+        if test == self.test {
+            return self.clone();
+        }
+        CliArguments {
+            test: test.to_owned(),
+            run: RunSelector::Latest,
+            ..self.clone()
+        }
+    }
 }
 
 pub fn cli_argument_parser() -> Options {
     let mut opts = Options::new();
     opts.optflag("h", "help", "print this help menu")
-        .reqopt(
+        .optopt(
             "d",
             "directory",
-            "path to the top folder of the crate being fuzzed, which must contain a 'fuzz' folder",
+            "path to the top folder of the crate being fuzzed, which must contain a 'fuzz' folder (default: the crate containing the current folder, or the only crate of the workspace with fuzz tests)",
             "<PATH>",
         )
-        .reqopt("t", "test", "name of the fuzz test", "(e.g. tests::fuzz)")
+        .optopt(
+            "t",
+            "test",
+            "name of the fuzz test (default: the only fuzz test of the crate)",
+            "(e.g. tests::fuzz)",
+        )
+        .optopt(
+            "r",
+            "run",
+            "stats folder to load: 'latest' (default), its name, or its index from the oldest run",
+            "<RUN>",
+        )
         .optopt(
             "w",
             "workspace",
-            "path to the cargo workspace containing the crate, if any",
+            "path to the cargo workspace containing the crate, if any (default: found with cargo metadata)",
+            "",
+        )
+        .optopt(
+            "",
+            "source-revision",
+            "read the source files as committed in this git revision instead of the working tree",
+            "<REVISION>",
+        )
+        .optflag(
+            "",
+            "source-snapshot",
+            "read the source files from the copy saved in the stats folder of the run by the snapshot command",
+        )
+        .optopt(
+            "",
+            "decoder",
+            "how to display the inputs: 'auto' (default), 'text', 'hex', 'serde-json', or 'pretty-json'",
+            "<DECODER>",
+        )
+        .optflag("", "json", "with the summary and patch commands, print the report as JSON")
+        .optopt(
+            "",
+            "min-coverage",
+            "with the summary command, fail if less than this percentage of the counters are hit; with the patch command, of the changed lines",
+            "<PERCENT>",
+        )
+        .optmulti(
+            "",
+            "require-hit",
+            "with the summary command, fail if this function is never hit (may be repeated)",
+            "<FUNCTION>",
+        )
+        .optflag(
+            "",
+            "merge-generics",
+            "show the instantiations of a generic function as a single function, whose code is hit if it is hit in any instantiation",
+        )
+        .optflag(
             "",
+            "watch",
+            "reload the coverage whenever fuzzcheck updates the stats or the corpus of the run being viewed",
+        )
+        .optopt(
+            "",
+            "address",
+            "address the server listens on (default: 127.0.0.1)",
+            "<ADDRESS>",
+        )
+        .optopt(
+            "",
+            "port",
+            "port the server listens on, or 0 to pick a free one (default: 8000)",
+            "<PORT>",
+        )
+        .optopt(
+            "",
+            "base-path",
+            "URL prefix under which the viewer is served, e.g. when it is behind a reverse proxy (default: /)",
+            "<PATH>",
+        )
+        .optopt(
+            "",
+            "html",
+            "with the export command, folder in which to write a static HTML report",
+            "<PATH>",
+        )
+        .optopt(
+            "",
+            "lcov",
+            "with the export command, path of the LCOV tracefile to write",
+            "<PATH>",
+        )
+        .optopt(
+            "",
+            "cobertura",
+            "with the export command, path of the Cobertura XML report to write",
+            "<PATH>",
         );
     opts
 }
@@ -31,27 +183,171 @@ pub fn parse_arguments(options: &Options, arguments: &[String]) -> CliArguments
     match options.parse(arguments) {
         Ok(matches) => {
             if matches.opt_present("help") {
-                println!("{}", options.usage("fuzzcheck-view -d DIRECTORY -t FUZZ_TARGET"));
+                println!("{}", options.usage(USAGE));
                 exit(0);
             }
-            let crate_directory = matches.opt_get::<PathBuf>("directory").unwrap().unwrap();
-            let test = matches.opt_get::<String>("test").unwrap().unwrap();
+            let (crate_directory, workspace_directory) = locate_crate(
+                matches.opt_get::<PathBuf>("directory").unwrap(),
+                matches.opt_get::<PathBuf>("workspace").unwrap(),
+            )
+            .unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                exit(1);
+            });
+            let test = match matches.opt_str("test") {
+                Some(test) => test,
+                None => fuzz_test(&crate_directory).unwrap_or_else(|e| {
+                    eprintln!("error: {}", e);
+                    exit(1);
+                }),
+            };
+            let run = matches
+                .opt_str("run")
+                .map_or(RunSelector::Latest, |run| RunSelector::from_string(&run));
+            let sources = match (
+                matches.opt_str("source-revision"),
+                matches.opt_present("source-snapshot"),
+            ) {
+                (None, false) => SourceOrigin::WorkingTree,
+                (Some(revision), false) => SourceOrigin::Revision(revision),
+                (None, true) => SourceOrigin::Snapshot,
+                (Some(_), true) => {
+                    eprintln!("error: --source-revision and --source-snapshot can't be used together");
+                    println!("{}", options.usage(USAGE));
+                    exit(1);
+                }
+            };
+            let decoder = match matches.opt_str("decoder") {
+                None => InputDecoder::default(),
+                Some(name) => InputDecoder::from_name(&name).unwrap_or_else(|| {
+                    eprintln!("error: unknown decoder {}", name);
+                    println!("{}", options.usage(USAGE));
+                    exit(1);
+                }),
+            };
+            let merge_generics = matches.opt_present("merge-generics");
+            let watch = matches.opt_present("watch");
+            let address = matches.opt_get::<IpAddr>("address").unwrap_or_else(|e| {
+                eprintln!("error: invalid address: {}", e);
+                println!("{}", options.usage(USAGE));
+                exit(1);
+            });
+            let port = matches.opt_get::<u16>("port").unwrap_or_else(|e| {
+                eprintln!("error: invalid port: {}", e);
+                println!("{}", options.usage(USAGE));
+                exit(1);
+            });
+            let base_path = base_path(matches.opt_str("base-path").as_deref().unwrap_or("/"));
 
-            let workspace_directory = matches
-                .opt_get::<PathBuf>("workspace")
-                .unwrap()
-                .unwrap_or(crate_directory.clone());
+            // the first free argument is the name of the executable
+            let command = match matches.free.get(1).map(String::as_str) {
+                None | Some("serve") => Command::Serve,
+                Some("tui") => Command::Tui,
+                Some("summary") => {
+                    let min_coverage = matches.opt_get::<f64>("min-coverage").unwrap_or_else(|e| {
+                        eprintln!("error: invalid minimum coverage: {}", e);
+                        println!("{}", options.usage(USAGE));
+                        exit(1);
+                    });
+                    Command::Summary {
+                        json: matches.opt_present("json"),
+                        requirements: Requirements {
+                            min_coverage,
+                            required_functions: matches.opt_strs("require-hit"),
+                        },
+                    }
+                }
+                Some("patch") => {
+                    let min_coverage = matches.opt_get::<f64>("min-coverage").unwrap_or_else(|e| {
+                        eprintln!("error: invalid minimum coverage: {}", e);
+                        println!("{}", options.usage(USAGE));
+                        exit(1);
+                    });
+                    Command::Patch {
+                        range: matches.free.get(2).cloned().unwrap_or_else(|| "HEAD".to_owned()),
+                        json: matches.opt_present("json"),
+                        min_coverage,
+                    }
+                }
+                Some("snapshot") => Command::Snapshot,
+                Some("export") => {
+                    let html = matches.opt_get::<PathBuf>("html").unwrap();
+                    let lcov = matches.opt_get::<PathBuf>("lcov").unwrap();
+                    let cobertura = matches.opt_get::<PathBuf>("cobertura").unwrap();
+                    if html.is_none() && lcov.is_none() && cobertura.is_none() {
+                        eprintln!(
+                            "error: the export command requires an output format: --html, --lcov, or --cobertura"
+                        );
+                        println!("{}", options.usage(USAGE));
+                        exit(1);
+                    }
+                    Command::Export { html, lcov, cobertura }
+                }
+                Some(command) => {
+                    eprintln!("error: unknown command {}", command);
+                    println!("{}", options.usage(USAGE));
+                    exit(1);
+                }
+            };
 
             CliArguments {
                 crate_directory,
                 test,
+                run,
+                sources,
+                decoder,
+                merge_generics,
+                watch,
+                address,
+                port,
+                base_path,
                 workspace_directory,
+                command,
             }
         }
         Err(e) => {
             eprintln!("error: {}", e);
-            println!("{}", options.usage("fuzzcheck-view -d DIRECTORY -t FUZZ_TARGET"));
+            println!("{}", options.usage(USAGE));
             exit(1);
         }
     }
 }
+
+/// Normalizes a URL prefix to `/` or `/segment/...` without a trailing slash.
+fn base_path(path: &str) -> String {
+    let segments = path.split('/').filter(|segment| !segment.is_empty());
+    let mut base_path = String::new();
+    for segment in segments {
+        base_path.push('/');
+        base_path.push_str(segment);
+    }
+    if base_path.is_empty() {
+        base_path.push('/');
+    }
+    base_path
+}
+
+/// Returns the folders of the crate being fuzzed and of its workspace, looking
+/// for the ones that are not given from the current folder with cargo metadata.
+///
+/// If the crate folder is given but cargo metadata fails, the crate is assumed
+/// to be its own workspace.
+fn locate_crate(
+    crate_directory: Option<PathBuf>,
+    workspace_directory: Option<PathBuf>,
+) -> Result<(PathBuf, PathBuf), Error> {
+    match (crate_directory, workspace_directory) {
+        (Some(crate_directory), Some(workspace_directory)) => Ok((crate_directory, workspace_directory)),
+        (Some(crate_directory), None) => {
+            let workspace_directory = Workspace::locate(&crate_directory)
+                .map_or_else(|_| crate_directory.clone(), |workspace| workspace.root);
+            Ok((crate_directory, workspace_directory))
+        }
+        (None, workspace_directory) => {
+            let current_directory = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+            let workspace = Workspace::locate(&current_directory)?;
+            let crate_directory = workspace.fuzzed_crate(&current_directory)?;
+            Ok((crate_directory, workspace_directory.unwrap_or(workspace.root)))
+        }
+    }
+}
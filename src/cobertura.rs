@@ -0,0 +1,272 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Write};
+use std::path::{Component, Path};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::fuzzcheck::{CoverageMap, Function, Region};
+use crate::html::escape;
+use crate::lcov::line_hits;
+
+/// The coverage of a single line of code, across all the functions containing it.
+#[derive(Clone, Copy, Default)]
+struct LineCoverage {
+    hit: bool,
+    /// number of distinct counters on the line
+    branches: usize,
+    /// number of distinct counters on the line that were hit
+    branches_hit: usize,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Totals {
+    lines: usize,
+    lines_hit: usize,
+    branches: usize,
+    branches_hit: usize,
+}
+
+impl Totals {
+    fn from_lines<'a>(lines: impl Iterator<Item = &'a LineCoverage>) -> Self {
+        let mut totals = Totals::default();
+        for line in lines {
+            totals.lines += 1;
+            totals.lines_hit += line.hit as usize;
+            // a line is only considered to be a branch if it contains more than one counter
+            if line.branches > 1 {
+                totals.branches += line.branches;
+                totals.branches_hit += line.branches_hit;
+            }
+        }
+        totals
+    }
+    fn add(&mut self, other: &Totals) {
+        self.lines += other.lines;
+        self.lines_hit += other.lines_hit;
+        self.branches += other.branches;
+        self.branches_hit += other.branches_hit;
+    }
+    fn rates(&self) -> String {
+        format!(
+            "line-rate=\"{:.4}\" branch-rate=\"{:.4}\"",
+            rate(self.lines_hit, self.lines),
+            rate(self.branches_hit, self.branches)
+        )
+    }
+}
+
+/// Writes the coverage of the functions in `coverage_map` as a Cobertura XML
+/// report.
+///
+/// Each source file is a class, and classes are grouped into packages named
+/// after their parent folder, relative to `workspace_directory`. The hit status of a line is computed by
+/// [`line_hits`], like in LCOV tracefiles. Lines covered by more than one
+/// counter are reported as branches, one per counter.
+pub fn write_cobertura(
+    coverage_map: &CoverageMap,
+    hit_counters: &HashSet<usize>,
+    workspace_directory: &Path,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    // package -> file -> functions
+    let mut packages = BTreeMap::<String, BTreeMap<String, Vec<&Function>>>::new();
+    for function in coverage_map.functions.iter() {
+        let file = function
+            .file
+            .strip_prefix(workspace_directory)
+            .unwrap_or(&function.file);
+        // only the names of the folders are kept, without the root or `..`
+        let package = file
+            .parent()
+            .map(|parent| {
+                parent
+                    .components()
+                    .filter_map(|c| match c {
+                        Component::Normal(name) => Some(name.to_string_lossy()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join(".")
+            })
+            .unwrap_or_default();
+        packages
+            .entry(package)
+            .or_default()
+            .entry(format!("{}", file.display()))
+            .or_default()
+            .push(function);
+    }
+
+    let mut packages_xml = String::new();
+    let mut total = Totals::default();
+    for (package, files) in packages.iter() {
+        let mut package_total = Totals::default();
+        let mut classes_xml = String::new();
+        for (file, functions) in files.iter() {
+            // all the regions of the file, with the index of their counter and whether they were hit
+            let mut file_regions = Vec::<(usize, Region, bool)>::new();
+            let mut methods = vec![];
+            for function in functions {
+                let mut regions = vec![];
+                for counter in function.common_counters() {
+                    let counter_idx = file_regions.len() + regions.len();
+                    let hit = counter.id.is_hit(hit_counters);
+                    regions.extend(counter.regions.into_iter().map(|region| (counter_idx, region, hit)));
+                }
+                let lines = regions_lines(&regions);
+                file_regions.extend(regions);
+                if let Some(&first_line) = lines.keys().next() {
+                    methods.push((first_line, function.function_name().demangled_name, lines));
+                }
+            }
+            methods.sort_by(|x, y| (x.0, &x.1).cmp(&(y.0, &y.1)));
+            let mut methods_xml = String::new();
+            for (first_line, name, lines) in methods {
+                methods_xml.push_str(&format!(
+                    "<method name=\"{}\" signature=\"\" {} complexity=\"0\" line=\"{}\">\n<lines>\n{}</lines>\n</method>\n",
+                    escape(&name),
+                    Totals::from_lines(lines.values()).rates(),
+                    first_line,
+                    lines_xml(&lines)
+                ));
+            }
+            let file_lines = regions_lines(&file_regions);
+            let file_total = Totals::from_lines(file_lines.values());
+            package_total.add(&file_total);
+            let class_name = Path::new(file)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| file.to_string());
+            classes_xml.push_str(&format!(
+                "<class name=\"{}\" filename=\"{}\" {} complexity=\"0\">\n<methods>\n{}</methods>\n<lines>\n{}</lines>\n</class>\n",
+                escape(&class_name),
+                escape(file),
+                file_total.rates(),
+                methods_xml,
+                lines_xml(&file_lines)
+            ));
+        }
+        total.add(&package_total);
+        packages_xml.push_str(&format!(
+            "<package name=\"{}\" {} complexity=\"0\">\n<classes>\n{}</classes>\n</package>\n",
+            escape(package),
+            package_total.rates(),
+            classes_xml
+        ));
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    writeln!(out, "<?xml version=\"1.0\" ?>")?;
+    writeln!(
+        out,
+        "<!DOCTYPE coverage SYSTEM \"http://cobertura.sourceforge.net/xml/coverage-04.dtd\">"
+    )?;
+    writeln!(
+        out,
+        "<coverage {} lines-covered=\"{}\" lines-valid=\"{}\" branches-covered=\"{}\" branches-valid=\"{}\" complexity=\"0\" version=\"fuzzcheck-view {}\" timestamp=\"{}\">",
+        total.rates(),
+        total.lines_hit,
+        total.lines,
+        total.branches_hit,
+        total.branches,
+        env!("CARGO_PKG_VERSION"),
+        timestamp
+    )?;
+    writeln!(
+        out,
+        "<sources>\n<source>{}</source>\n</sources>",
+        escape(&format!("{}", workspace_directory.display()))
+    )?;
+    write!(out, "<packages>\n{}</packages>\n", packages_xml)?;
+    writeln!(out, "</coverage>")?;
+    Ok(())
+}
+
+/// Returns the coverage of every line covered by the given regions, each with
+/// the index of its counter and whether it was hit.
+fn regions_lines(regions: &[(usize, Region, bool)]) -> BTreeMap<usize, LineCoverage> {
+    line_hits(regions.iter().map(|(_, region, hit)| (region, *hit)))
+        .into_iter()
+        .map(|(lineno, hit)| {
+            let counters = regions
+                .iter()
+                .filter(|(_, region, _)| region.lines.0 <= lineno && lineno <= region.lines.1)
+                .map(|(counter_idx, _, hit)| (*counter_idx, *hit))
+                .collect::<BTreeMap<_, _>>();
+            let coverage = LineCoverage {
+                hit,
+                branches: counters.len(),
+                branches_hit: counters.values().filter(|hit| **hit).count(),
+            };
+            (lineno, coverage)
+        })
+        .collect()
+}
+
+fn lines_xml(lines: &BTreeMap<usize, LineCoverage>) -> String {
+    let mut xml = String::new();
+    for (lineno, line) in lines.iter() {
+        if line.branches > 1 {
+            xml.push_str(&format!(
+                "<line number=\"{}\" hits=\"{}\" branch=\"true\" condition-coverage=\"{}% ({}/{})\"/>\n",
+                lineno,
+                line.hit as usize,
+                line.branches_hit * 100 / line.branches,
+                line.branches_hit,
+                line.branches
+            ));
+        } else {
+            xml.push_str(&format!(
+                "<line number=\"{}\" hits=\"{}\" branch=\"false\"/>\n",
+                lineno, line.hit as usize
+            ));
+        }
+    }
+    xml
+}
+
+fn rate(covered: usize, valid: usize) -> f64 {
+    if valid == 0 {
+        1.0
+    } else {
+        covered as f64 / valid as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzcheck::Counter;
+
+    #[test]
+    fn packages_and_files_are_relative_to_the_workspace() {
+        let function = |file: &str| Function {
+            name: "_ZN4lexer4next17h0000000000000000E".to_owned(),
+            file: file.into(),
+            counters: vec![Counter {
+                id: 0,
+                regions: vec![Region {
+                    lines: (1, 3),
+                    cols: (1, 2),
+                }],
+            }],
+            inferred_counters: vec![],
+        };
+        let coverage_map = CoverageMap {
+            functions: vec![
+                function("/tmp/ws/src/parser/lexer.rs"),
+                function("/tmp/ws/src/../generated/tokens.rs"),
+            ],
+        };
+        let mut out = vec![];
+        write_cobertura(&coverage_map, &HashSet::from([0]), Path::new("/tmp/ws"), &mut out).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert!(xml.contains("<source>/tmp/ws</source>"));
+        assert!(xml.contains("<package name=\"src.parser\""));
+        assert!(xml.contains("<class name=\"lexer.rs\" filename=\"src/parser/lexer.rs\""));
+        assert!(xml.contains("<package name=\"src.generated\""));
+        assert!(xml.contains("filename=\"src/../generated/tokens.rs\""));
+    }
+}
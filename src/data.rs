@@ -0,0 +1,296 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+
+use crate::args::CliArguments;
+use crate::error::{Error, Result};
+use crate::fuzzcheck::{read_input_corpus, CorpusMap, CoverageMap, SerializedUniqCov};
+use crate::index::RunIndex;
+use crate::sources::SourceFiles;
+use crate::FunctionCoverage;
+
+/// Everything fuzzcheck saved about a fuzz test: the coverage map of the
+/// instrumented code, the `simplest_cov` pool and the corpus.
+pub struct FuzzTestData {
+    /// name of the fuzz test
+    pub test: String,
+    pub stats_folder: PathBuf,
+    pub coverage_map: CoverageMap,
+    pub simplest_cov: SerializedUniqCov,
+    pub corpus_map: CorpusMap,
+    pub all_inputs: HashMap<String, Vec<u8>>,
+    pub index: RunIndex,
+    pub sources: SourceFiles,
+}
+
+/// Which of the stats folders of a fuzz test to load. Each time fuzzcheck
+/// runs, it saves its stats in a new folder named after the current time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RunSelector {
+    Latest,
+    /// name of the stats folder of the run
+    Name(String),
+    /// index of the run, from the oldest to the most recent
+    Index(usize),
+}
+
+impl RunSelector {
+    /// Parses `latest`, the index of a run, or the name of a stats folder. A
+    /// number is always taken as an index, as fuzzcheck names the stats
+    /// folders after dates.
+    pub fn from_string(s: &str) -> Self {
+        match s {
+            "latest" => RunSelector::Latest,
+            s => match s.parse() {
+                Ok(idx) => RunSelector::Index(idx),
+                Err(_) => RunSelector::Name(s.to_owned()),
+            },
+        }
+    }
+
+    /// Returns the stats folder selected among `runs`, which must be sorted.
+    pub fn select<'a>(&self, runs: &'a [PathBuf]) -> Option<&'a PathBuf> {
+        match self {
+            RunSelector::Latest => runs.last(),
+            RunSelector::Name(name) => runs
+                .iter()
+                .find(|run| run.file_name().is_some_and(|n| n == name.as_str())),
+            &RunSelector::Index(idx) => runs.get(idx),
+        }
+    }
+}
+
+impl fmt::Display for RunSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunSelector::Latest => write!(f, "latest"),
+            RunSelector::Name(name) => write!(f, "{}", name),
+            RunSelector::Index(idx) => write!(f, "at index {}", idx),
+        }
+    }
+}
+
+/// Returns the names of the fuzz tests of the crate for which fuzzcheck saved
+/// stats, sorted by name.
+pub fn fuzz_tests(crate_directory: &Path) -> Result<Vec<String>> {
+    let fuzz_folder = crate_directory.join("fuzz");
+    let read_error = |error| Error::Read {
+        path: fuzz_folder.clone(),
+        description: "fuzz folder of the crate",
+        error,
+    };
+    let mut tests = vec![];
+    for directory in std::fs::read_dir(&fuzz_folder).map_err(read_error)? {
+        let directory = directory.map_err(read_error)?;
+        if directory.path().join("stats").is_dir() {
+            tests.push(directory.file_name().to_string_lossy().into_owned());
+        }
+    }
+    tests.sort();
+    Ok(tests)
+}
+
+/// Returns the stats folders of the fuzz test, from the oldest to the most recent.
+pub fn stats_runs(crate_directory: &Path, test: &str) -> Result<Vec<PathBuf>> {
+    let stats_folder = crate_directory.join("fuzz").join(test).join("stats");
+    let read_error = |error| Error::Read {
+        path: stats_folder.clone(),
+        description: "stats folder of the fuzz test",
+        error,
+    };
+    let mut stats_folders = vec![];
+    for directory in std::fs::read_dir(&stats_folder).map_err(read_error)? {
+        let directory = directory.map_err(read_error)?;
+        if directory.file_type().map_err(read_error)?.is_dir() {
+            stats_folders.push(directory.path());
+        }
+    }
+    stats_folders.sort();
+    Ok(stats_folders)
+}
+
+/// Returns the name of the stats folder of a run.
+pub fn run_name(stats_folder: &Path) -> String {
+    stats_folder
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Returns the stats folder selected by `run` among the runs of the fuzz test.
+pub fn find_run(crate_directory: &Path, test: &str, run: &RunSelector) -> Result<PathBuf> {
+    let runs = stats_runs(crate_directory, test)?;
+    if runs.is_empty() {
+        return Err(Error::NoRuns {
+            stats_folder: crate_directory.join("fuzz").join(test).join("stats"),
+        });
+    }
+    run.select(&runs).cloned().ok_or_else(|| Error::UnknownRun {
+        run: run.to_string(),
+        test: test.to_owned(),
+    })
+}
+
+/// The code coverage reached during a run, without its corpus.
+pub struct RunCoverage {
+    pub stats_folder: PathBuf,
+    pub coverage_map: CoverageMap,
+    pub hit_counters: HashSet<usize>,
+}
+
+impl RunCoverage {
+    /// Loads the coverage saved in `stats_folder`, where the paths of the
+    /// source files are relative to `source_folder`.
+    pub fn load(stats_folder: &Path, source_folder: &Path) -> Result<Self> {
+        let coverage_map = read_coverage_map(stats_folder, source_folder)?;
+        let simplest_cov = read_simplest_cov(stats_folder)?;
+        Ok(RunCoverage {
+            stats_folder: stats_folder.to_path_buf(),
+            coverage_map,
+            hit_counters: simplest_cov.all_hit_counters.into_iter().collect(),
+        })
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path, description: &'static str) -> Result<T> {
+    let bytes = std::fs::read(path).map_err(|error| Error::Read {
+        path: path.to_path_buf(),
+        description,
+        error,
+    })?;
+    serde_json::from_slice(&bytes).map_err(|error| Error::Parse {
+        path: path.to_path_buf(),
+        description,
+        error,
+    })
+}
+
+fn read_coverage_map(stats_folder: &Path, source_folder: &Path) -> Result<CoverageMap> {
+    let coverage_map_path = stats_folder.join("coverage_sensor.json");
+    let mut coverage_map: CoverageMap = read_json(&coverage_map_path, "coverage map")?;
+    coverage_map.map_relative_paths(|path| {
+        *path = source_folder.join(&path);
+    });
+    Ok(coverage_map)
+}
+
+fn read_simplest_cov(stats_folder: &Path) -> Result<SerializedUniqCov> {
+    read_json(&stats_folder.join("simplest_cov.json"), "simplest_cov pool")
+}
+
+impl FuzzTestData {
+    /// Loads the stats folder of the fuzz test described by `args` that is
+    /// selected by `args.run`.
+    pub fn load(args: &CliArguments) -> Result<Self> {
+        let stats_folder = find_run(&args.crate_directory, &args.test, &args.run)?;
+        Self::load_run(args, &stats_folder)
+    }
+
+    /// Loads the given stats folder of the fuzz test described by `args`.
+    pub fn load_run(args: &CliArguments, stats_folder: &Path) -> Result<Self> {
+        let CliArguments {
+            crate_directory,
+            test: fuzz_test,
+            workspace_directory: source_folder,
+            ..
+        } = args;
+
+        let fuzz_folder = crate_directory.join("fuzz").join(fuzz_test);
+        let stats_folder = stats_folder.to_path_buf();
+
+        let coverage_map = read_coverage_map(&stats_folder, source_folder)?;
+        let simplest_cov = read_simplest_cov(&stats_folder)?;
+        let corpus_map: CorpusMap = read_json(&stats_folder.join("world.json"), "corpus map")?;
+        let all_inputs = read_input_corpus(&fuzz_folder.join("corpus"))?;
+        let index = RunIndex::new(&simplest_cov, &corpus_map);
+        let sources = SourceFiles::load(
+            &args.sources,
+            coverage_map.functions.iter().map(|f| f.file.as_path()),
+            source_folder,
+            &stats_folder,
+        )?;
+        for path in sources.stale() {
+            eprintln!(
+                "warning: {} was modified since the run, its coverage may be shown on the wrong code \
+                 (see --source-revision and --source-snapshot)",
+                path.display()
+            );
+        }
+
+        Ok(FuzzTestData {
+            test: fuzz_test.clone(),
+            stats_folder,
+            coverage_map,
+            simplest_cov,
+            corpus_map,
+            all_inputs,
+            index,
+            sources,
+        })
+    }
+
+    /// Returns the coverage of every function, with the status of each span
+    /// computed from the counters hit by all the inputs of the pool.
+    pub fn functions(&self) -> Result<Vec<FunctionCoverage>> {
+        let mut cov_functions = self.coverage_map.functions(&self.sources)?;
+        for function in cov_functions.iter_mut() {
+            function.set_hit_statuses(&self.index.all_hit_counters);
+        }
+        Ok(cov_functions)
+    }
+
+    /// Returns the name of the corpus file of the input at `pool_idx`.
+    pub fn input_hash(&self, pool_idx: usize) -> Option<&str> {
+        self.index.input_hash(pool_idx).ok()
+    }
+
+    /// Returns the pool index of the least complex input reaching `counter`.
+    pub fn best_input_for_counter(&self, counter: usize) -> Option<usize> {
+        self.index.best_input_for_counter.get(&counter).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runs() -> Vec<PathBuf> {
+        ["stats/2022-07-01-10-00", "stats/2022-07-02-10-00", "stats/5"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    #[test]
+    fn numbers_are_parsed_as_indices() {
+        assert_eq!(RunSelector::from_string("latest"), RunSelector::Latest);
+        assert_eq!(RunSelector::from_string("1"), RunSelector::Index(1));
+        assert_eq!(
+            RunSelector::from_string("2022-07-01-10-00"),
+            RunSelector::Name("2022-07-01-10-00".to_owned())
+        );
+    }
+
+    #[test]
+    fn run_is_selected_by_name_or_by_index() {
+        let runs = runs();
+        assert_eq!(RunSelector::Latest.select(&runs), Some(&runs[2]));
+        assert_eq!(
+            RunSelector::Name("2022-07-02-10-00".to_owned()).select(&runs),
+            Some(&runs[1])
+        );
+        assert_eq!(RunSelector::Index(0).select(&runs), Some(&runs[0]));
+        assert_eq!(RunSelector::Index(3).select(&runs), None);
+        assert_eq!(RunSelector::Name("missing".to_owned()).select(&runs), None);
+    }
+
+    #[test]
+    fn folder_named_with_digits_is_not_taken_for_an_index() {
+        let runs = runs();
+        assert_eq!(RunSelector::Name("5".to_owned()).select(&runs), Some(&runs[2]));
+        assert_eq!(RunSelector::Index(5).select(&runs), None);
+        assert_eq!(RunSelector::Index(1).select(&runs), Some(&runs[1]));
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt::Write;
+
+#[cfg(feature = "server")]
+use rocket::form::FromFormField;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{self, Error};
+
+/// number of bytes shown on each line of a hex dump
+const HEX_LINE_LENGTH: usize = 16;
+
+/// How to turn the bytes of an input of the corpus into something readable.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(FromFormField))]
+#[serde(rename_all = "kebab-case")]
+pub enum InputDecoder {
+    /// `Text` for text inputs and `Hex` for binary inputs
+    #[default]
+    Auto,
+    /// UTF-8 text, with invalid sequences replaced by U+FFFD
+    Text,
+    /// offsets, bytes and ASCII characters, 16 bytes per line
+    Hex,
+    /// the format of fuzzcheck's `SerdeSerializer`, i.e. the input serialized
+    /// with `serde_json`, shown on a single line
+    #[cfg_attr(feature = "server", field(value = "serde-json"))]
+    SerdeJson,
+    /// the same format as `SerdeJson`, indented
+    #[cfg_attr(feature = "server", field(value = "pretty-json"))]
+    PrettyJson,
+}
+
+/// An input of the corpus decoded with an [`InputDecoder`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DecodedInput {
+    pub decoder: InputDecoder,
+    /// the input rendered as text by the decoder
+    pub text: String,
+    /// the structure of the input, for the decoders reading JSON
+    pub value: Option<serde_json::Value>,
+}
+
+impl InputDecoder {
+    /// Parses the name of a decoder, as given on the command line.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(InputDecoder::Auto),
+            "text" => Some(InputDecoder::Text),
+            "hex" => Some(InputDecoder::Hex),
+            "serde-json" => Some(InputDecoder::SerdeJson),
+            "pretty-json" => Some(InputDecoder::PrettyJson),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputDecoder::Auto => "auto",
+            InputDecoder::Text => "text",
+            InputDecoder::Hex => "hex",
+            InputDecoder::SerdeJson => "serde-json",
+            InputDecoder::PrettyJson => "pretty-json",
+        }
+    }
+
+    /// Decodes the content of a corpus file. Only the JSON decoders can fail.
+    ///
+    /// The decoder of the returned input is never `Auto`.
+    pub fn decode(&self, input: &[u8]) -> error::Result<DecodedInput> {
+        let (text, value) = match self {
+            InputDecoder::Auto if is_binary(input) => return InputDecoder::Hex.decode(input),
+            InputDecoder::Auto => return InputDecoder::Text.decode(input),
+            InputDecoder::Text => (String::from_utf8_lossy(input).into_owned(), None),
+            InputDecoder::Hex => (hex_dump(input), None),
+            InputDecoder::SerdeJson | InputDecoder::PrettyJson => {
+                let value = serde_json::from_slice::<serde_json::Value>(input).map_err(|error| Error::Decode {
+                    decoder: self.name(),
+                    error,
+                })?;
+                let text = if *self == InputDecoder::PrettyJson {
+                    serde_json::to_string_pretty(&value)
+                } else {
+                    serde_json::to_string(&value)
+                }
+                .expect("a JSON value can always be serialized");
+                (text, Some(value))
+            }
+        };
+        Ok(DecodedInput {
+            decoder: *self,
+            text,
+            value,
+        })
+    }
+}
+
+/// Returns true if the input is not UTF-8 text, or if it contains control
+/// characters other than whitespace.
+pub fn is_binary(input: &[u8]) -> bool {
+    match std::str::from_utf8(input) {
+        Ok(text) => text.chars().any(|c| c.is_control() && !c.is_ascii_whitespace()),
+        Err(_) => true,
+    }
+}
+
+fn hex_dump(input: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line_idx, line) in input.chunks(HEX_LINE_LENGTH).enumerate() {
+        let _ = write!(dump, "{:08x} ", line_idx * HEX_LINE_LENGTH);
+        for byte_idx in 0..HEX_LINE_LENGTH {
+            match line.get(byte_idx) {
+                Some(byte) => {
+                    let _ = write!(dump, " {:02x}", byte);
+                }
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push_str("  |");
+        dump.extend(line.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        }));
+        dump.push_str("|\n");
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_decoded_as_utf8() {
+        let input = "héllo\n\twörld".as_bytes();
+        assert!(!is_binary(input));
+        let decoded = InputDecoder::Auto.decode(input).unwrap();
+        assert_eq!(decoded.decoder, InputDecoder::Text);
+        assert_eq!(decoded.text, "héllo\n\twörld");
+        assert_eq!(decoded.value, None);
+    }
+
+    #[test]
+    fn invalid_utf8_and_control_characters_are_binary() {
+        assert!(is_binary(&[0x66, 0xff, 0x6f]));
+        assert!(is_binary(b"ab\0cd"));
+        assert!(is_binary(b"\x1b[0m"));
+        assert!(!is_binary(b""));
+        assert!(!is_binary(b"a b\r\n"));
+        let decoded = InputDecoder::Auto.decode(&[0x66, 0xff]).unwrap();
+        assert_eq!(decoded.decoder, InputDecoder::Hex);
+        // the text decoder replaces the invalid sequences instead
+        assert_eq!(InputDecoder::Text.decode(&[0x66, 0xff]).unwrap().text, "f\u{fffd}");
+    }
+
+    #[test]
+    fn hex_dump_shows_offsets_bytes_and_ascii() {
+        let input = b"0123456789abcdef\x00\x7f A";
+        assert_eq!(
+            hex_dump(input),
+            "00000000  30 31 32 33 34 35 36 37 38 39 61 62 63 64 65 66  |0123456789abcdef|\n\
+             00000010  00 7f 20 41                                      |.. A|\n"
+        );
+        assert_eq!(hex_dump(b""), "");
+    }
+
+    #[test]
+    fn json_is_parsed_and_reformatted() {
+        let input = br#"{"a": [1,  2]}"#;
+        let decoded = InputDecoder::SerdeJson.decode(input).unwrap();
+        assert_eq!(decoded.text, r#"{"a":[1,2]}"#);
+        assert_eq!(decoded.value, Some(serde_json::json!({ "a": [1, 2] })));
+        let pretty = InputDecoder::PrettyJson.decode(input).unwrap();
+        assert_eq!(pretty.text, "{\n  \"a\": [\n    1,\n    2\n  ]\n}");
+    }
+
+    #[test]
+    fn invalid_json_is_a_decode_error() {
+        // the server answers a decode error with 422 Unprocessable Entity
+        let error = InputDecoder::PrettyJson.decode(b"{\"a\": ").unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Decode {
+                decoder: "pretty-json",
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::fuzzcheck::{CoverageMap, Region};
+use crate::{CodeSpanKind, CoverageStatus, FunctionCoverage, FunctionName};
+
+/// Identifies a counter by its location in the source code, since the ids of
+/// the counters may differ between two fuzz targets or two builds.
+pub(crate) type CounterLocation = (PathBuf, Vec<(usize, usize, usize, usize)>);
+
+pub(crate) fn counter_location(file: &Path, regions: &[Region]) -> CounterLocation {
+    (
+        file.to_path_buf(),
+        regions
+            .iter()
+            .map(|r| (r.lines.0, r.cols.0, r.lines.1, r.cols.1))
+            .collect(),
+    )
+}
+
+/// The counters gained and lost between a base run and a new run, expressed in
+/// terms of the counter ids of the new run.
+pub struct CoverageDiff {
+    /// counters of the new run that were hit by the base run
+    base_hit: HashSet<usize>,
+    /// counters of the new run that were hit by the new run
+    new_hit: HashSet<usize>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DiffSummary {
+    /// number of counters hit by the new run but not by the base run
+    pub gained: usize,
+    /// number of counters hit by the base run but not by the new run
+    pub lost: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FunctionDiff {
+    pub name: FunctionName,
+    pub diff: DiffSummary,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub file: String,
+    pub diff: DiffSummary,
+    pub functions: Vec<FunctionDiff>,
+}
+
+impl DiffSummary {
+    pub fn add(&mut self, other: &DiffSummary) {
+        self.gained += other.gained;
+        self.lost += other.lost;
+    }
+    pub fn is_empty(&self) -> bool {
+        self.gained == 0 && self.lost == 0
+    }
+}
+
+impl CoverageDiff {
+    /// Compares the counters hit by two runs. Counters of the base run are
+    /// matched with the counters of the new run covering the same regions of
+    /// the same file. Counters of the base run that don't exist in the new run
+    /// are ignored.
+    pub fn new(
+        base_map: &CoverageMap,
+        base_hit_counters: &HashSet<usize>,
+        new_map: &CoverageMap,
+        new_hit_counters: &HashSet<usize>,
+    ) -> Self {
+        let mut base_locations = HashMap::<CounterLocation, bool>::new();
+        for function in base_map.functions.iter() {
+            for counter in function.counters.iter() {
+                let hit = base_hit_counters.contains(&counter.id);
+                *base_locations
+                    .entry(counter_location(&function.file, &counter.regions))
+                    .or_default() |= hit;
+            }
+        }
+        let mut base_hit = HashSet::new();
+        for function in new_map.functions.iter() {
+            for counter in function.counters.iter() {
+                let location = counter_location(&function.file, &counter.regions);
+                if base_locations.get(&location).copied().unwrap_or(false) {
+                    base_hit.insert(counter.id);
+                }
+            }
+        }
+        CoverageDiff {
+            base_hit,
+            new_hit: new_hit_counters.clone(),
+        }
+    }
+
+    fn status(hit_by_base: bool, hit_by_new: bool) -> CoverageStatus {
+        match (hit_by_base, hit_by_new) {
+            (true, true) => CoverageStatus::Hit,
+            (false, true) => CoverageStatus::NewlyHit,
+            (true, false) => CoverageStatus::NoLongerHit,
+            (false, false) => CoverageStatus::NotHit,
+        }
+    }
+
+    /// Returns the status of a counter of the new run.
+    pub fn counter_status(&self, id: usize) -> CoverageStatus {
+        Self::status(self.base_hit.contains(&id), self.new_hit.contains(&id))
+    }
+
+    /// Sets the status of every span of the function to reflect whether it was
+    /// gained or lost by the new run.
+    pub fn apply(&self, coverage: &mut FunctionCoverage) {
+        for line in coverage.lines.iter_mut() {
+            for span in line.spans.iter_mut() {
+                match &mut span.kind {
+                    CodeSpanKind::Untracked => {}
+                    CodeSpanKind::Inferred { inferred_from, status } => {
+                        *status = Self::status(
+                            inferred_from.iter().any(|id| self.base_hit.contains(id)),
+                            inferred_from.iter().any(|id| self.new_hit.contains(id)),
+                        );
+                    }
+                    CodeSpanKind::Tracked { id, status } => {
+                        *status = self.counter_status(*id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the number of counters gained and lost in every file and
+    /// function of the new run, sorted by file and function name.
+    pub fn summaries(&self, new_map: &CoverageMap) -> Vec<FileDiff> {
+        let mut files = BTreeMap::<String, Vec<FunctionDiff>>::new();
+        for function in new_map.functions.iter() {
+            let mut diff = DiffSummary::default();
+            for counter in function.counters.iter() {
+                match self.counter_status(counter.id) {
+                    CoverageStatus::NewlyHit => diff.gained += 1,
+                    CoverageStatus::NoLongerHit => diff.lost += 1,
+                    _ => {}
+                }
+            }
+            files
+                .entry(format!("{}", function.file.display()))
+                .or_default()
+                .push(FunctionDiff {
+                    name: function.function_name(),
+                    diff,
+                });
+        }
+        files
+            .into_iter()
+            .map(|(file, mut functions)| {
+                functions.sort_by(|x, y| x.name.demangled_name.cmp(&y.name.demangled_name));
+                let mut diff = DiffSummary::default();
+                for function in functions.iter() {
+                    diff.add(&function.diff);
+                }
+                FileDiff { file, diff, functions }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzcheck::{Counter, Function};
+
+    fn coverage_map(counters: &[(usize, usize)]) -> CoverageMap {
+        CoverageMap {
+            functions: vec![Function {
+                name: "_ZN8my_crate7process17h0000000000000001E".to_owned(),
+                file: PathBuf::from("src/lib.rs"),
+                counters: counters
+                    .iter()
+                    .map(|&(id, line)| Counter {
+                        id,
+                        regions: vec![Region {
+                            lines: (line, line),
+                            cols: (5, 20),
+                        }],
+                    })
+                    .collect(),
+                inferred_counters: vec![],
+            }],
+        }
+    }
+
+    #[test]
+    fn counters_are_matched_by_location() {
+        // the counters of the lines 1 to 4 exist in both runs with other ids,
+        // the counter of line 5 only in the new run, and of line 6 only in the base
+        let base_map = coverage_map(&[(0, 1), (1, 2), (2, 3), (3, 4), (4, 6)]);
+        let new_map = coverage_map(&[(13, 1), (12, 2), (11, 3), (10, 4), (14, 5)]);
+        let base_hit = HashSet::from([0, 1, 4]);
+        let new_hit = HashSet::from([12, 11, 14]);
+        let diff = CoverageDiff::new(&base_map, &base_hit, &new_map, &new_hit);
+
+        assert_eq!(diff.counter_status(13), CoverageStatus::NoLongerHit);
+        assert_eq!(diff.counter_status(12), CoverageStatus::Hit);
+        assert_eq!(diff.counter_status(11), CoverageStatus::NewlyHit);
+        assert_eq!(diff.counter_status(10), CoverageStatus::NotHit);
+        assert_eq!(diff.counter_status(14), CoverageStatus::NewlyHit);
+
+        let summaries = diff.summaries(&new_map);
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].file, "src/lib.rs");
+        assert_eq!((summaries[0].diff.gained, summaries[0].diff.lost), (2, 1));
+        assert_eq!(
+            (
+                summaries[0].functions[0].diff.gained,
+                summaries[0].functions[0].diff.lost
+            ),
+            (2, 1)
+        );
+    }
+}
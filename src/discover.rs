@@ -0,0 +1,132 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Deserialize;
+
+use crate::data::fuzz_tests;
+use crate::error::{Error, Result};
+
+/// The packages of a cargo workspace, as listed by `cargo metadata`.
+#[derive(Clone, Debug)]
+pub struct Workspace {
+    pub root: PathBuf,
+    /// the folder of each package of the workspace
+    pub packages: Vec<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    workspace_root: PathBuf,
+    packages: Vec<Package>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    manifest_path: PathBuf,
+}
+
+impl Workspace {
+    /// Runs `cargo metadata` in `directory` to find the workspace containing it.
+    pub fn locate(directory: &Path) -> Result<Self> {
+        let error = |message: String| Error::CargoMetadata {
+            directory: directory.to_path_buf(),
+            message,
+        };
+        let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+        let output = Command::new(cargo)
+            .args(["metadata", "--no-deps", "--format-version", "1"])
+            .current_dir(directory)
+            .output()
+            .map_err(|e| error(e.to_string()))?;
+        if !output.status.success() {
+            return Err(error(String::from_utf8_lossy(&output.stderr).trim().to_owned()));
+        }
+        let metadata: Metadata = serde_json::from_slice(&output.stdout).map_err(|e| error(e.to_string()))?;
+        let packages = metadata
+            .packages
+            .into_iter()
+            .filter_map(|package| package.manifest_path.parent().map(Path::to_path_buf))
+            .collect();
+        Ok(Workspace {
+            root: metadata.workspace_root,
+            packages,
+        })
+    }
+
+    /// Returns the crate being fuzzed: the package containing `directory` if
+    /// it has fuzz tests, and otherwise the only package with fuzz tests.
+    pub fn fuzzed_crate(&self, directory: &Path) -> Result<PathBuf> {
+        let directory = directory.canonicalize().unwrap_or_else(|_| directory.to_path_buf());
+        let has_fuzz_tests = |package: &&PathBuf| fuzz_tests(package).is_ok_and(|tests| !tests.is_empty());
+        // packages may be nested, in which case the innermost one contains the directory
+        let containing = self
+            .packages
+            .iter()
+            .filter(|package| directory.starts_with(package))
+            .max_by_key(|package| package.components().count());
+        if let Some(package) = containing.filter(has_fuzz_tests) {
+            return Ok(package.clone());
+        }
+        let mut fuzzed = self.packages.iter().filter(has_fuzz_tests).cloned().collect::<Vec<_>>();
+        match fuzzed.len() {
+            0 => Err(Error::NoFuzzTest {
+                directory: self.root.clone(),
+            }),
+            1 => Ok(fuzzed.remove(0)),
+            _ => Err(Error::AmbiguousCrate { candidates: fuzzed }),
+        }
+    }
+}
+
+/// Returns the only fuzz test of the crate with saved stats.
+pub fn fuzz_test(crate_directory: &Path) -> Result<String> {
+    let mut tests = fuzz_tests(crate_directory).unwrap_or_default();
+    match tests.len() {
+        0 => Err(Error::NoFuzzTest {
+            directory: crate_directory.to_path_buf(),
+        }),
+        1 => Ok(tests.remove(0)),
+        _ => Err(Error::AmbiguousTest { candidates: tests }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a workspace whose root is a package, with a member crate in
+    /// `member`. Only the packages listed in `fuzzed` have fuzz tests.
+    fn workspace(name: &str, fuzzed: &[&str]) -> Workspace {
+        let root = std::env::temp_dir().join(format!("fuzzcheck-view-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let member = root.join("member");
+        for package in [&root, &member] {
+            std::fs::create_dir_all(package.join("src")).unwrap();
+        }
+        for package in fuzzed {
+            std::fs::create_dir_all(root.join(package).join("fuzz/tests::fuzz/stats")).unwrap();
+        }
+        let root = root.canonicalize().unwrap();
+        Workspace {
+            packages: vec![root.clone(), root.join("member")],
+            root,
+        }
+    }
+
+    #[test]
+    fn containing_package_with_fuzz_tests_is_fuzzed() {
+        let workspace = workspace("containing", &["", "member"]);
+        let member = workspace.root.join("member");
+        assert_eq!(workspace.fuzzed_crate(&member.join("src")).unwrap(), member);
+        assert_eq!(workspace.fuzzed_crate(&workspace.root).unwrap(), workspace.root);
+        std::fs::remove_dir_all(&workspace.root).unwrap();
+    }
+
+    #[test]
+    fn containing_package_without_fuzz_tests_falls_back_to_the_fuzzed_one() {
+        let workspace = workspace("fallback", &["member"]);
+        let member = workspace.root.join("member");
+        assert_eq!(workspace.fuzzed_crate(&workspace.root).unwrap(), member);
+        std::fs::remove_dir_all(&workspace.root).unwrap();
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    /// a file or folder that fuzzcheck should have created could not be read
+    Read {
+        path: PathBuf,
+        /// what the file is supposed to contain
+        description: &'static str,
+        error: std::io::Error,
+    },
+    /// a file created by fuzzcheck could not be parsed
+    Parse {
+        path: PathBuf,
+        description: &'static str,
+        error: serde_json::Error,
+    },
+    /// a path that should be a folder is a file
+    NotAFolder {
+        path: PathBuf,
+        description: &'static str,
+    },
+    /// the source file of an instrumented function could not be read
+    SourceFile {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    NoRuns {
+        stats_folder: PathBuf,
+    },
+    /// `cargo metadata` failed while looking for the crate being fuzzed
+    CargoMetadata {
+        directory: PathBuf,
+        message: String,
+    },
+    /// git failed while computing the coverage of a patch or reading a source
+    /// file from a revision
+    Git {
+        directory: PathBuf,
+        message: String,
+    },
+    /// no fuzz test with saved stats was found in the crate or the workspace
+    NoFuzzTest {
+        directory: PathBuf,
+    },
+    /// several crates of the workspace have fuzz tests and none was chosen
+    AmbiguousCrate {
+        candidates: Vec<PathBuf>,
+    },
+    /// the crate has several fuzz tests and none was chosen
+    AmbiguousTest {
+        candidates: Vec<String>,
+    },
+    UnknownRun {
+        run: String,
+        test: String,
+    },
+    UnknownTarget {
+        test: String,
+    },
+    UnknownInput {
+        pool_idx: usize,
+    },
+    UnknownInputHash {
+        hash: String,
+    },
+    UnknownFunction {
+        name: String,
+    },
+    /// no function of the coverage map is in the file
+    UnknownFile {
+        file: String,
+    },
+    UnknownCounter {
+        id: usize,
+    },
+    /// the query of a search is not a valid pattern
+    InvalidPattern {
+        pattern: String,
+        error: regex::Error,
+    },
+    /// git does not understand the revision range of a patch
+    InvalidRange {
+        range: String,
+        message: String,
+    },
+    /// an input could not be decoded with the decoder chosen by the user
+    Decode {
+        decoder: &'static str,
+        error: serde_json::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Returns true if the error is caused by a request for something that
+    /// does not exist, rather than by a problem with the files on disk.
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            Error::UnknownRun { .. }
+                | Error::UnknownTarget { .. }
+                | Error::UnknownInput { .. }
+                | Error::UnknownInputHash { .. }
+                | Error::UnknownFunction { .. }
+                | Error::UnknownFile { .. }
+                | Error::UnknownCounter { .. }
+        )
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Read {
+                path,
+                description,
+                error,
+            } => write!(f, "can't read the {} at {}: {}", description, path.display(), error),
+            Error::Parse {
+                path,
+                description,
+                error,
+            } => write!(f, "can't parse the {} at {}: {}", description, path.display(), error),
+            Error::NotAFolder { path, description } => {
+                write!(f, "the {} at {} should be a folder", description, path.display())
+            }
+            Error::SourceFile { path, error } => write!(
+                f,
+                "can't read the source file {}: {} (is the workspace folder given with -w correct?)",
+                path.display(),
+                error
+            ),
+            Error::NoRuns { stats_folder } => write!(
+                f,
+                "there are no runs in {}, has fuzzcheck been launched on this fuzz test?",
+                stats_folder.display()
+            ),
+            Error::CargoMetadata { directory, message } => write!(
+                f,
+                "can't find the cargo workspace containing {}, pass the crate folder with -d: {}",
+                directory.display(),
+                message
+            ),
+            Error::Git { directory, message } => {
+                write!(f, "git failed in {}: {}", directory.display(), message)
+            }
+            Error::NoFuzzTest { directory } => write!(
+                f,
+                "there is no fuzz test with saved stats in {}, has fuzzcheck been launched?",
+                directory.display()
+            ),
+            Error::AmbiguousCrate { candidates } => {
+                write!(f, "several crates have fuzz tests, choose one with -d:")?;
+                for candidate in candidates {
+                    write!(f, "\n    {}", candidate.display())?;
+                }
+                Ok(())
+            }
+            Error::AmbiguousTest { candidates } => {
+                write!(f, "the crate has several fuzz tests, choose one with -t:")?;
+                for candidate in candidates {
+                    write!(f, "\n    {}", candidate)?;
+                }
+                Ok(())
+            }
+            Error::UnknownRun { run, test } => write!(f, "there is no run {} for the fuzz test {}", run, test),
+            Error::UnknownTarget { test } => write!(f, "there is no fuzz test {} with saved stats", test),
+            Error::UnknownInput { pool_idx } => write!(f, "there is no input {} in the pool", pool_idx),
+            Error::UnknownInputHash { hash } => write!(f, "there is no input named {} in the corpus", hash),
+            Error::UnknownFunction { name } => write!(f, "there is no function named {}", name),
+            Error::UnknownFile { file } => write!(f, "there is no instrumented code in {}", file),
+            Error::UnknownCounter { id } => write!(f, "no input reaches the counter {}", id),
+            Error::InvalidPattern { pattern, error } => write!(f, "invalid search pattern {}: {}", pattern, error),
+            Error::InvalidRange { range, message } => write!(f, "invalid revision range {}: {}", range, message),
+            Error::Decode { decoder, error } => write!(f, "can't decode the input as {}: {}", decoder, error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Read { error, .. } | Error::SourceFile { error, .. } => Some(error),
+            Error::Parse { error, .. } | Error::Decode { error, .. } => Some(error),
+            Error::InvalidPattern { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -5,6 +5,8 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Error, Result};
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CoverageMap {
     pub functions: Vec<Function>,
@@ -70,27 +72,37 @@ pub struct SerializedUniqCov {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CorpusMap(pub Vec<((PathBuf, usize), String)>);
 
-pub fn read_input_corpus(dir: &Path) -> HashMap<String, Vec<u8>> {
+pub fn read_input_corpus(dir: &Path) -> Result<HashMap<String, Vec<u8>>> {
     let mut values = HashMap::new();
-    read_input_corpus_rec(dir, &mut values);
-    values
+    read_input_corpus_rec(dir, &mut values)?;
+    Ok(values)
 }
 
-fn read_input_corpus_rec(corpus: &Path, values: &mut HashMap<String, Vec<u8>>) {
+fn read_input_corpus_rec(corpus: &Path, values: &mut HashMap<String, Vec<u8>>) -> Result<()> {
     if !corpus.exists() {
-        return;
+        return Ok(());
     }
     if !corpus.is_dir() {
-        panic!()
+        return Err(Error::NotAFolder {
+            path: corpus.to_path_buf(),
+            description: "corpus",
+        });
     }
-    for entry in std::fs::read_dir(corpus).unwrap() {
-        let entry = entry.unwrap();
+    let read_error = |path: &Path, error| Error::Read {
+        path: path.to_path_buf(),
+        description: "corpus",
+        error,
+    };
+    for entry in std::fs::read_dir(corpus).map_err(|e| read_error(corpus, e))? {
+        let entry = entry.map_err(|e| read_error(corpus, e))?;
         let path = entry.path();
         if path.is_dir() {
-            read_input_corpus_rec(&path, values);
+            read_input_corpus_rec(&path, values)?;
         } else {
-            let data = std::fs::read(&path).unwrap();
-            values.insert(path.file_stem().unwrap().to_str().unwrap().to_owned(), data);
+            let data = std::fs::read(&path).map_err(|e| read_error(&path, e))?;
+            let Some(name) = path.file_stem() else { continue };
+            values.insert(name.to_string_lossy().into_owned(), data);
         }
     }
+    Ok(())
 }
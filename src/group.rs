@@ -0,0 +1,239 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::diff::{counter_location, CounterLocation};
+use crate::fuzzcheck::{CoverageMap, Function};
+use crate::FunctionName;
+
+/// The functions of the coverage map located at the same place in the same
+/// source file, which are the instantiations of a generic function.
+///
+/// The functions of a group have the same counter regions but different
+/// counter ids. A non-generic function is alone in its group, unless the
+/// coverage map contains it twice.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FunctionGroup {
+    pub file: String,
+    /// line on which the first region of the functions starts
+    pub line: usize,
+    /// the instantiation whose counter ids are used for the merged coverage
+    /// of the group
+    pub representative: FunctionName,
+    /// the distinct instantiations, sorted by demangled name
+    pub instantiations: Vec<FunctionName>,
+    /// indices of the functions of the group in the coverage map, which may
+    /// contain the same instantiation several times
+    #[serde(skip)]
+    pub function_idxs: Vec<usize>,
+}
+
+/// Groups the functions of the coverage map by their location, sorted by file
+/// and line.
+pub fn group_functions(coverage_map: &CoverageMap) -> Vec<FunctionGroup> {
+    let mut groups = BTreeMap::<(String, usize, usize), Vec<usize>>::new();
+    let mut ungrouped = vec![];
+    for (function_idx, function) in coverage_map.functions.iter().enumerate() {
+        let start = function
+            .counters
+            .iter()
+            .flat_map(|c| c.regions.iter())
+            .chain(function.inferred_counters.iter().flat_map(|c| c.regions.iter()))
+            .map(|region| (region.lines.0, region.cols.0))
+            .min();
+        match start {
+            Some((line, col)) => groups
+                .entry((format!("{}", function.file.display()), line, col))
+                .or_default()
+                .push(function_idx),
+            // a function without regions can't be located
+            None => ungrouped.push(function_idx),
+        }
+    }
+    let mut groups = groups
+        .into_iter()
+        .map(|((file, line, _), function_idxs)| new_group(coverage_map, file, line, function_idxs))
+        .collect::<Vec<_>>();
+    for function_idx in ungrouped {
+        let file = format!("{}", coverage_map.functions[function_idx].file.display());
+        groups.push(new_group(coverage_map, file, 0, vec![function_idx]));
+    }
+    groups
+}
+
+fn new_group(coverage_map: &CoverageMap, file: String, line: usize, function_idxs: Vec<usize>) -> FunctionGroup {
+    let mut instantiations = function_idxs
+        .iter()
+        .map(|&idx| coverage_map.functions[idx].function_name())
+        .collect::<Vec<_>>();
+    instantiations.sort_by(|x, y| x.demangled_name.cmp(&y.demangled_name));
+    instantiations.dedup();
+    FunctionGroup {
+        file,
+        line,
+        representative: coverage_map.functions[function_idxs[0]].function_name(),
+        instantiations,
+        function_idxs,
+    }
+}
+
+impl FunctionGroup {
+    /// Returns the name shown for the merged coverage of the group: the path
+    /// of the function without the hash added by the compiler, followed by
+    /// the number of instantiations if there are several.
+    pub fn merged_name(&self) -> FunctionName {
+        let path = self.representative.path();
+        let demangled_name = if self.instantiations.len() > 1 {
+            format!("{} [{} instantiations]", path, self.instantiations.len())
+        } else {
+            path
+        };
+        FunctionName {
+            name: self.representative.name.clone(),
+            demangled_name,
+        }
+    }
+
+    /// Returns the functions of the group whose mangled name is `name`.
+    pub fn functions_named<'a>(&'a self, coverage_map: &'a CoverageMap, name: &'a str) -> Vec<&'a Function> {
+        self.function_idxs
+            .iter()
+            .map(|&idx| &coverage_map.functions[idx])
+            .filter(|function| function.name == name)
+            .collect()
+    }
+
+    pub fn functions<'a>(&'a self, coverage_map: &'a CoverageMap) -> Vec<&'a Function> {
+        self.function_idxs
+            .iter()
+            .map(|&idx| &coverage_map.functions[idx])
+            .collect()
+    }
+}
+
+/// Maps the counters of the first function to the counters of all the given
+/// functions covering the same regions, including themselves.
+///
+/// The functions should be instantiations of the same generic function, for
+/// example the functions of a [`FunctionGroup`].
+#[derive(Clone, Debug, Default)]
+pub struct EquivalentCounters {
+    counters: HashMap<usize, Vec<usize>>,
+}
+
+impl EquivalentCounters {
+    pub fn new(functions: &[&Function]) -> Self {
+        let Some((first, _)) = functions.split_first() else {
+            return EquivalentCounters::default();
+        };
+        let mut by_location = HashMap::<CounterLocation, Vec<usize>>::new();
+        for function in functions {
+            for counter in function.counters.iter() {
+                by_location
+                    .entry(counter_location(&function.file, &counter.regions))
+                    .or_default()
+                    .push(counter.id);
+            }
+        }
+        let counters = first
+            .counters
+            .iter()
+            .map(|counter| {
+                let location = counter_location(&first.file, &counter.regions);
+                let equivalents = by_location.remove(&location).unwrap_or_else(|| vec![counter.id]);
+                (counter.id, equivalents)
+            })
+            .collect();
+        EquivalentCounters { counters }
+    }
+
+    /// Returns true if the counter of the first function, or one of its
+    /// equivalent counters, satisfies the predicate.
+    pub fn any(&self, id: usize, mut predicate: impl FnMut(usize) -> bool) -> bool {
+        match self.counters.get(&id) {
+            Some(equivalents) => equivalents.iter().any(|&id| predicate(id)),
+            None => predicate(id),
+        }
+    }
+
+    /// Returns the counters of the first function that are in `counters`
+    /// or whose equivalent counters are.
+    pub fn merge(&self, counters: &HashSet<usize>) -> HashSet<usize> {
+        let mut merged = counters.clone();
+        for (&id, equivalents) in self.counters.iter() {
+            if equivalents.iter().any(|id| counters.contains(id)) {
+                merged.insert(id);
+            }
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::fuzzcheck::{Counter, Region};
+    use crate::Position;
+
+    /// Returns a function with one counter per id, covering the region from
+    /// the first to the second position.
+    fn function(name: &str, counters: &[(usize, Position, Position)]) -> Function {
+        Function {
+            name: name.to_owned(),
+            file: PathBuf::from("src/lib.rs"),
+            counters: counters
+                .iter()
+                .map(|&(id, start, end)| Counter {
+                    id,
+                    regions: vec![Region {
+                        lines: (start.0, end.0),
+                        cols: (start.1, end.1),
+                    }],
+                })
+                .collect(),
+            inferred_counters: vec![],
+        }
+    }
+
+    #[test]
+    fn counter_hit_in_one_instantiation_is_hit_in_the_merged_view() {
+        let coverage_map = CoverageMap {
+            functions: vec![
+                function(
+                    "_ZN8my_crate7process17h0000000000000001E",
+                    &[(0, (3, 1), (8, 2)), (1, (4, 9), (6, 6))],
+                ),
+                function("_ZN8my_crate5other17h0000000000000003E", &[(2, (10, 1), (12, 2))]),
+                function(
+                    "_ZN8my_crate7process17h0000000000000002E",
+                    &[(5, (3, 1), (8, 2)), (6, (4, 9), (6, 6))],
+                ),
+            ],
+        };
+
+        let groups = group_functions(&coverage_map);
+        assert_eq!(groups.len(), 2);
+        let group = &groups[0];
+        assert_eq!((group.file.as_str(), group.line), ("src/lib.rs", 3));
+        assert_eq!(group.function_idxs, vec![0, 2]);
+        assert_eq!(group.instantiations.len(), 2);
+        assert_eq!(
+            group.merged_name().demangled_name,
+            "my_crate::process [2 instantiations]"
+        );
+        assert_eq!(groups[1].function_idxs, vec![1]);
+        assert_eq!(groups[1].merged_name().demangled_name, "my_crate::other");
+
+        let functions = group.functions(&coverage_map);
+        let equivalents = EquivalentCounters::new(&functions);
+        let hit_counters = HashSet::from([6]);
+        assert!(equivalents.any(1, |id| hit_counters.contains(&id)));
+        assert!(!equivalents.any(0, |id| hit_counters.contains(&id)));
+        assert_eq!(equivalents.merge(&hit_counters), HashSet::from([1, 6]));
+
+        let summary = functions[0].summary(&equivalents.merge(&hit_counters));
+        assert_eq!((summary.tracked_counters, summary.hit_counters), (2, 1));
+    }
+}
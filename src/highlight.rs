@@ -0,0 +1,295 @@
+use serde::{Deserialize, Serialize};
+
+/// The Rust tokens that the viewers color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenClass {
+    Keyword,
+    /// string, byte string, raw string and character literals
+    String,
+    /// line, block and doc comments
+    Comment,
+    /// the name of an invoked macro, with its `!`
+    Macro,
+    Lifetime,
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self",
+    "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while", "yield",
+];
+
+/// The class of each character of a source file, line by line.
+#[derive(Clone, Debug, Default)]
+pub struct Highlighting {
+    lines: Vec<Vec<Option<TokenClass>>>,
+}
+
+impl Highlighting {
+    /// Lexes a whole source file, so that tokens spanning several lines, such
+    /// as block comments and strings, are recognized.
+    pub fn new(source: &str) -> Self {
+        let chars = source
+            .lines()
+            .collect::<Vec<_>>()
+            .join("\n")
+            .chars()
+            .collect::<Vec<_>>();
+        let mut classes = vec![None; chars.len()];
+        let mut i = 0;
+        while i < chars.len() {
+            let start = i;
+            let (end, class) = token(&chars, i);
+            i = end.max(start + 1);
+            classes[start..i].fill(class);
+        }
+        let mut lines = vec![vec![]];
+        for (c, class) in chars.iter().zip(classes) {
+            if *c == '\n' {
+                lines.push(vec![]);
+            } else {
+                lines.last_mut().unwrap().push(class);
+            }
+        }
+        Highlighting { lines }
+    }
+
+    /// Splits `text`, which starts at the character `col` of the 0-based line
+    /// `line`, into pieces made of a single class of token.
+    pub fn split(&self, line: usize, col: usize, text: &str) -> Vec<(String, Option<TokenClass>)> {
+        let classes = self.lines.get(line).map(Vec::as_slice).unwrap_or_default();
+        let mut pieces = Vec::<(String, Option<TokenClass>)>::new();
+        for (offset, c) in text.chars().enumerate() {
+            let class = classes.get(col + offset).copied().flatten();
+            match pieces.last_mut() {
+                Some((piece, piece_class)) if *piece_class == class => piece.push(c),
+                _ => pieces.push((c.to_string(), class)),
+            }
+        }
+        if pieces.is_empty() {
+            pieces.push((String::new(), None));
+        }
+        pieces
+    }
+}
+
+/// Returns the end of the token starting at `i` and its class.
+fn token(chars: &[char], i: usize) -> (usize, Option<TokenClass>) {
+    let at = |idx: usize| chars.get(idx).copied().unwrap_or('\0');
+    match (at(i), at(i + 1)) {
+        ('/', '/') => {
+            let end = (i..chars.len()).find(|&idx| chars[idx] == '\n').unwrap_or(chars.len());
+            (end, Some(TokenClass::Comment))
+        }
+        ('/', '*') => (block_comment_end(chars, i), Some(TokenClass::Comment)),
+        ('"', _) => (string_end(chars, i + 1), Some(TokenClass::String)),
+        ('b' | 'c', '"') => (string_end(chars, i + 2), Some(TokenClass::String)),
+        ('b', '\'') => (char_end(chars, i + 1), Some(TokenClass::String)),
+        ('\'', next) => {
+            if next == '\\' || at(i + 2) == '\'' {
+                (char_end(chars, i), Some(TokenClass::String))
+            } else if is_ident_start(next) {
+                (ident_end(chars, i + 1), Some(TokenClass::Lifetime))
+            } else {
+                (i + 1, None)
+            }
+        }
+        // raw identifiers are never keywords
+        ('r', '#') if is_ident_start(at(i + 2)) => (ident_end(chars, i + 2), None),
+        (c, _) if is_ident_start(c) => {
+            if let Some(end) = raw_string_end(chars, i) {
+                return (end, Some(TokenClass::String));
+            }
+            let end = ident_end(chars, i);
+            if at(end) == '!' && at(end + 1) != '=' {
+                return (end + 1, Some(TokenClass::Macro));
+            }
+            let word = chars[i..end].iter().collect::<String>();
+            (end, KEYWORDS.contains(&word.as_str()).then_some(TokenClass::Keyword))
+        }
+        // numbers are skipped whole so that their suffixes are not taken for identifiers
+        (c, _) if c.is_ascii_digit() => (ident_end(chars, i), None),
+        _ => (i + 1, None),
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c == '_' || c.is_alphabetic()
+}
+
+fn ident_end(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() && (chars[i] == '_' || chars[i].is_alphanumeric()) {
+        i += 1;
+    }
+    i
+}
+
+/// Returns the end of a string whose content starts at `i`.
+fn string_end(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
+
+/// Returns the end of a character literal whose opening quote is at `i`.
+fn char_end(chars: &[char], mut i: usize) -> usize {
+    i += 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '\'' => return i + 1,
+            '\n' => return i,
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
+
+/// Returns the end of the raw string starting at `i`, if there is one.
+fn raw_string_end(chars: &[char], i: usize) -> Option<usize> {
+    let mut j = i;
+    if matches!(chars[j], 'b' | 'c') {
+        j += 1;
+    }
+    if chars.get(j) != Some(&'r') {
+        return None;
+    }
+    j += 1;
+    let hashes = chars[j..].iter().take_while(|&&c| c == '#').count();
+    j += hashes;
+    if chars.get(j) != Some(&'"') {
+        return None;
+    }
+    j += 1;
+    while j < chars.len() {
+        if chars[j] == '"' && chars[j + 1..].iter().take(hashes).filter(|&&c| c == '#').count() == hashes {
+            return Some(j + 1 + hashes);
+        }
+        j += 1;
+    }
+    Some(chars.len())
+}
+
+/// Returns the end of the block comment starting at `i`, which may contain
+/// other block comments.
+fn block_comment_end(chars: &[char], mut i: usize) -> usize {
+    let mut depth = 0;
+    while i < chars.len() {
+        match (chars[i], chars.get(i + 1)) {
+            ('/', Some('*')) => {
+                depth += 1;
+                i += 2;
+            }
+            ('*', Some('/')) => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the pieces of the first line of `source` that belong to a token.
+    fn tokens(source: &str) -> Vec<(String, TokenClass)> {
+        let line = source.lines().next().unwrap_or_default();
+        Highlighting::new(source)
+            .split(0, 0, line)
+            .into_iter()
+            .filter_map(|(text, class)| Some((text, class?)))
+            .collect()
+    }
+
+    fn token(text: &str, class: TokenClass) -> (String, TokenClass) {
+        (text.to_owned(), class)
+    }
+
+    #[test]
+    fn character_is_not_a_lifetime() {
+        assert_eq!(
+            tokens("fn f<'a>(x: &'a u8) -> char { 'a' }"),
+            vec![
+                token("fn", TokenClass::Keyword),
+                token("'a", TokenClass::Lifetime),
+                token("'a", TokenClass::Lifetime),
+                token("'a'", TokenClass::String),
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_string_ends_with_its_hashes() {
+        assert_eq!(
+            tokens(r##"let s = r#"a "b" c"# + x;"##),
+            vec![
+                token("let", TokenClass::Keyword),
+                token(r##"r#"a "b" c"#"##, TokenClass::String)
+            ]
+        );
+    }
+
+    #[test]
+    fn block_comments_nest() {
+        assert_eq!(
+            tokens("/* a /* b */ c */ let"),
+            vec![
+                token("/* a /* b */ c */", TokenClass::Comment),
+                token("let", TokenClass::Keyword)
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_quote_in_byte_character() {
+        assert_eq!(
+            tokens(r"let q = b'\''; mut"),
+            vec![
+                token("let", TokenClass::Keyword),
+                token(r"b'\''", TokenClass::String),
+                token("mut", TokenClass::Keyword),
+            ]
+        );
+    }
+
+    #[test]
+    fn inequality_is_not_a_macro() {
+        assert_eq!(tokens("x != y"), vec![]);
+    }
+
+    #[test]
+    fn macro_name_includes_its_bang() {
+        assert_eq!(
+            tokens("let v = vec![1, 2];"),
+            vec![token("let", TokenClass::Keyword), token("vec!", TokenClass::Macro)]
+        );
+    }
+
+    #[test]
+    fn tokens_span_several_lines() {
+        let highlighting = Highlighting::new("let s = \"a\nb\"; // c\nfn");
+        assert_eq!(
+            highlighting.split(1, 0, "b\"; // c"),
+            vec![
+                ("b\"".to_owned(), Some(TokenClass::String)),
+                ("; ".to_owned(), None),
+                ("// c".to_owned(), Some(TokenClass::Comment)),
+            ]
+        );
+        assert_eq!(
+            highlighting.split(2, 0, "fn"),
+            vec![("fn".to_owned(), Some(TokenClass::Keyword))]
+        );
+    }
+}
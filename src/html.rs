@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::Path;
+
+use crate::data::FuzzTestData;
+use crate::decode::InputDecoder;
+use crate::{CodeSpanKind, CoverageStatus, FunctionCoverage};
+
+/// maximum number of characters of an input shown in its preview
+const PREVIEW_LENGTH: usize = 2000;
+
+const STYLE: &str = "
+body { background: #1a1f29; color: #cccac2; font-family: sans-serif; margin: 2em; }
+a { color: #5ccfec; }
+table { border-collapse: collapse; }
+th, td { padding: 0.2em 1em; text-align: left; }
+pre, code { font-family: monospace; }
+.source td { padding: 0 0.5em; vertical-align: top; }
+.source { background: #242936; }
+.lineno { color: rgba(138, 145, 153, 0.6); text-align: right; user-select: none; }
+.tracked.Hit { color: #87d96c; background: rgba(135, 217, 108, 0.2); }
+.tracked.NotHit { color: #ff6666; background: rgba(255, 102, 102, 0.2); }
+.inferred.Hit { color: #87d96c; }
+.inferred.NotHit { color: #ff6666; }
+.Keyword { color: #ffad66; }
+.String { color: #d5ff80; }
+.Comment { color: #b8cfe6; font-style: italic; }
+.Macro { color: #f28779; }
+.Lifetime { color: #dfbfff; }
+.input { background: #242936; padding: 1em; white-space: pre-wrap; word-break: break-all; }
+";
+
+/// Writes a self-contained static website showing the coverage of the given
+/// functions of the fuzz test to `out_dir`.
+///
+/// The website contains an index of all files, one page per file listing its
+/// functions, and one page per function with its annotated source code and the
+/// least complex input reaching each of its hit counters.
+pub fn export_html(data: &FuzzTestData, functions: &[FunctionCoverage], out_dir: &Path) -> io::Result<()> {
+    let all_hit_counters = &data.index.all_hit_counters;
+
+    let mut functions_per_file = BTreeMap::<&str, Vec<usize>>::new();
+    for (function_idx, function) in functions.iter().enumerate() {
+        functions_per_file
+            .entry(function.file.as_str())
+            .or_default()
+            .push(function_idx);
+    }
+
+    std::fs::create_dir_all(out_dir.join("files"))?;
+    std::fs::create_dir_all(out_dir.join("functions"))?;
+
+    let mut index = String::new();
+    index.push_str("<h1>Files</h1>\n<table>\n<tr><th>File</th><th>Functions</th><th>Counters hit</th></tr>\n");
+    for (file_idx, (file, function_idxs)) in functions_per_file.iter().enumerate() {
+        let (hit, tracked) = function_idxs
+            .iter()
+            .map(|&idx| functions[idx].counters_hit(all_hit_counters))
+            .fold((0, 0), |(h1, t1), (h2, t2)| (h1 + h2, t1 + t2));
+        index.push_str(&format!(
+            "<tr><td><a href=\"files/{}.html\">{}</a></td><td>{}</td><td>{} / {}</td></tr>\n",
+            file_idx,
+            escape(file),
+            function_idxs.len(),
+            hit,
+            tracked
+        ));
+
+        let mut file_page = String::new();
+        file_page.push_str(&format!(
+            "<p><a href=\"../index.html\">all files</a></p>\n<h1>{}</h1>\n<table>\n<tr><th>Function</th><th>Counters hit</th></tr>\n",
+            escape(file)
+        ));
+        for &function_idx in function_idxs {
+            let function = &functions[function_idx];
+            let (hit, tracked) = function.counters_hit(all_hit_counters);
+            file_page.push_str(&format!(
+                "<tr><td><a href=\"../functions/{}.html\"><code>{}</code></a></td><td>{} / {}</td></tr>\n",
+                function_idx,
+                escape(&function.name.demangled_name),
+                hit,
+                tracked
+            ));
+            let function_page = function_page(data, function, file_idx);
+            std::fs::write(
+                out_dir.join("functions").join(format!("{}.html", function_idx)),
+                page(&function.name.demangled_name, &function_page),
+            )?;
+        }
+        file_page.push_str("</table>\n");
+        std::fs::write(
+            out_dir.join("files").join(format!("{}.html", file_idx)),
+            page(file, &file_page),
+        )?;
+    }
+    index.push_str("</table>\n");
+    std::fs::write(out_dir.join("index.html"), page("fuzzcheck coverage", &index))?;
+    Ok(())
+}
+
+fn function_page(data: &FuzzTestData, function: &FunctionCoverage, file_idx: usize) -> String {
+    let mut html = String::new();
+    html.push_str(&format!(
+        "<p><a href=\"../index.html\">all files</a> / <a href=\"../files/{}.html\">{}</a></p>\n<h1><code>{}</code></h1>\n",
+        file_idx,
+        escape(&function.file),
+        escape(&function.name.demangled_name)
+    ));
+
+    let mut best_inputs = BTreeSet::new();
+    html.push_str("<table class=\"source\">\n");
+    for line in function.lines.iter() {
+        html.push_str(&format!("<tr><td class=\"lineno\">{}</td><td><pre>", line.lineno));
+        for span in line.spans.iter() {
+            let text = match span.token {
+                Some(token) => format!("<span class=\"{:?}\">{}</span>", token, escape(&span.text)),
+                None => escape(&span.text),
+            };
+            match &span.kind {
+                CodeSpanKind::Untracked => html.push_str(&text),
+                CodeSpanKind::Inferred { status, .. } => {
+                    html.push_str(&format!("<span class=\"inferred {:?}\">{}</span>", status, text));
+                }
+                CodeSpanKind::Tracked { id, status } => {
+                    let best_input = if matches!(status, CoverageStatus::NotHit) {
+                        None
+                    } else {
+                        data.best_input_for_counter(*id)
+                    };
+                    if let Some(pool_idx) = best_input {
+                        best_inputs.insert(pool_idx);
+                        html.push_str(&format!(
+                            "<a class=\"tracked {:?}\" href=\"#input-{}\" title=\"{}\">{}</a>",
+                            status,
+                            pool_idx,
+                            escape(&input_preview(data, pool_idx)),
+                            text
+                        ));
+                    } else {
+                        html.push_str(&format!("<span class=\"tracked {:?}\">{}</span>", status, text));
+                    }
+                }
+            }
+        }
+        html.push_str("</pre></td></tr>\n");
+    }
+    html.push_str("</table>\n");
+
+    if !best_inputs.is_empty() {
+        html.push_str("<h2>Least complex inputs</h2>\n");
+        for pool_idx in best_inputs {
+            let hash = data.input_hash(pool_idx).unwrap_or("unknown input");
+            html.push_str(&format!(
+                "<h3 id=\"input-{}\"><code>{}</code></h3>\n<pre class=\"input\">{}</pre>\n",
+                pool_idx,
+                escape(hash),
+                escape(&input_preview(data, pool_idx))
+            ));
+        }
+    }
+    html
+}
+
+fn input_preview(data: &FuzzTestData, pool_idx: usize) -> String {
+    let input = data.input_hash(pool_idx).and_then(|hash| data.all_inputs.get(hash));
+    match input {
+        Some(input) => {
+            // binary inputs are shown as a hex dump
+            let input = match InputDecoder::Auto.decode(input) {
+                Ok(decoded) => decoded.text,
+                Err(_) => String::from_utf8_lossy(input).into_owned(),
+            };
+            // control characters are not allowed in HTML documents
+            let mut preview = input
+                .chars()
+                .take(PREVIEW_LENGTH)
+                .map(|c| {
+                    if c.is_control() && c != '\n' && c != '\t' {
+                        char::REPLACEMENT_CHARACTER
+                    } else {
+                        c
+                    }
+                })
+                .collect::<String>();
+            if input.chars().count() > PREVIEW_LENGTH {
+                preview.push('…');
+            }
+            preview
+        }
+        None => "input not found in the corpus".to_owned(),
+    }
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"UTF-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
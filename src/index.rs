@@ -0,0 +1,103 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::error::{Error, Result};
+use crate::fuzzcheck::{CorpusMap, SerializedUniqCov};
+use crate::{CoverageKindFilter, InputFilter};
+
+/// Hash indexes over the pool and the corpus of a run, built once when the run
+/// is loaded so that the requests of the viewer don't scan them.
+#[derive(Clone, Debug, Default)]
+pub struct RunIndex {
+    pub all_hit_counters: HashSet<usize>,
+    /// counter -> pool index of the least complex input hitting it
+    pub best_input_for_counter: HashMap<usize, usize>,
+    /// pool index -> counters for which the input is the least complex one
+    pub least_complex_counters_for_input: HashMap<usize, HashSet<usize>>,
+    /// pool index -> counters hit by the input
+    pub counters_for_input: HashMap<usize, HashSet<usize>>,
+    /// counter -> number of inputs of the pool hitting it
+    pub inputs_per_counter: HashMap<usize, usize>,
+    /// pool index -> name of the corpus file of the input
+    pub input_hashes: HashMap<usize, String>,
+}
+
+impl RunIndex {
+    pub fn new(simplest_cov: &SerializedUniqCov, corpus_map: &CorpusMap) -> Self {
+        let mut index = RunIndex {
+            all_hit_counters: simplest_cov.all_hit_counters.iter().copied().collect(),
+            best_input_for_counter: simplest_cov.best_for_counter.iter().copied().collect(),
+            ..RunIndex::default()
+        };
+        for &(counter, pool_idx) in simplest_cov.best_for_counter.iter() {
+            index
+                .least_complex_counters_for_input
+                .entry(pool_idx)
+                .or_default()
+                .insert(counter);
+        }
+        for (pool_idx, counters) in simplest_cov.counters_for_input.iter() {
+            let counters = counters.iter().copied().collect::<HashSet<_>>();
+            for &counter in counters.iter() {
+                *index.inputs_per_counter.entry(counter).or_default() += 1;
+            }
+            index.counters_for_input.insert(*pool_idx, counters);
+        }
+        for ((_, pool_idx), hash) in corpus_map.0.iter() {
+            index.input_hashes.insert(*pool_idx, hash.clone());
+        }
+        index
+    }
+
+    /// Returns the name of the corpus file of the input at `pool_idx`.
+    pub fn input_hash(&self, pool_idx: usize) -> Result<&str> {
+        self.input_hashes
+            .get(&pool_idx)
+            .map(String::as_str)
+            .ok_or(Error::UnknownInput { pool_idx })
+    }
+
+    /// Returns the counters reached by the input at `pool_idx` and by no other
+    /// input of the pool.
+    pub fn unique_counters_for_input(&self, pool_idx: usize) -> HashSet<usize> {
+        self.counters_for_input
+            .get(&pool_idx)
+            .into_iter()
+            .flatten()
+            .filter(|counter| self.inputs_per_counter.get(counter) == Some(&1))
+            .copied()
+            .collect()
+    }
+
+    /// Returns true if the input at `pool_idx` is the least complex input
+    /// hitting `counter`.
+    pub fn is_best_input_for_counter(&self, pool_idx: usize, counter: usize) -> bool {
+        self.best_input_for_counter.get(&counter) == Some(&pool_idx)
+    }
+
+    /// Returns the counters that should be considered hit given the input and
+    /// coverage kind filters.
+    pub fn filtered_counters(
+        &self,
+        input_filter: &InputFilter,
+        coverage_kind_filter: &CoverageKindFilter,
+    ) -> Result<HashSet<usize>> {
+        match input_filter {
+            InputFilter::All => Ok(self.all_hit_counters.clone()),
+            &InputFilter::Input(pool_idx) => {
+                let all_input_counters = self
+                    .counters_for_input
+                    .get(&pool_idx)
+                    .ok_or(Error::UnknownInput { pool_idx })?;
+                Ok(match coverage_kind_filter {
+                    CoverageKindFilter::All => all_input_counters.clone(),
+                    CoverageKindFilter::LeastComplex => self
+                        .least_complex_counters_for_input
+                        .get(&pool_idx)
+                        .map(|least_complex| all_input_counters.intersection(least_complex).copied().collect())
+                        .unwrap_or_default(),
+                    CoverageKindFilter::Unique => self.unique_counters_for_input(pool_idx),
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::fuzzcheck::{CoverageMap, Function, Region};
+
+/// Returns whether each line covered by `regions` was hit.
+///
+/// Like in llvm-cov, a line is hit if one of the regions starting on it was
+/// hit, or if the innermost region wrapping it, i.e. starting on a previous
+/// line, was hit. For example, the line `a(); if x { b(); }` is hit if `a()`
+/// ran, even if the branch did not.
+pub(crate) fn line_hits<'a>(regions: impl IntoIterator<Item = (&'a Region, bool)>) -> BTreeMap<usize, bool> {
+    let regions = regions.into_iter().collect::<Vec<_>>();
+    let mut starting = BTreeMap::<usize, bool>::new();
+    for (region, hit) in regions.iter() {
+        *starting.entry(region.lines.0).or_default() |= *hit;
+    }
+    let first_line = regions.iter().map(|(r, _)| r.lines.0).min().unwrap_or(1);
+    let last_line = regions.iter().map(|(r, _)| r.lines.1).max().unwrap_or(0);
+    let mut lines = BTreeMap::new();
+    for line in first_line..=last_line {
+        let wrapping = regions
+            .iter()
+            .filter(|(r, _)| r.lines.0 < line && line <= r.lines.1)
+            .max_by_key(|(r, _)| (r.lines.0, r.cols.0))
+            .map(|(_, hit)| *hit);
+        let hit = match (starting.get(&line), wrapping) {
+            (None, None) => continue,
+            (starting, wrapping) => starting.copied().unwrap_or(false) || wrapping.unwrap_or(false),
+        };
+        lines.insert(line, hit);
+    }
+    lines
+}
+
+/// Writes the coverage of the functions in `coverage_map` as an LCOV tracefile.
+///
+/// fuzzcheck does not count how many times a counter was hit, so every hit line,
+/// function, and branch is reported with an execution count of 1.
+///
+/// The hit status of a line is computed by [`line_hits`]. Lines on which more
+/// than one region starts are reported as branches, one per region.
+pub fn write_lcov(
+    coverage_map: &CoverageMap,
+    hit_counters: &HashSet<usize>,
+    test_name: &str,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut functions_per_file = BTreeMap::<&Path, Vec<&Function>>::new();
+    for function in coverage_map.functions.iter() {
+        functions_per_file.entry(&function.file).or_default().push(function);
+    }
+
+    // test names may only contain letters, digits and underscores
+    let test_name = test_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+
+    for (file, functions) in functions_per_file {
+        writeln!(out, "TN:{}", test_name)?;
+        writeln!(out, "SF:{}", file.display())?;
+
+        // all the regions of the file, with the index of their function and whether they were hit
+        let mut regions = Vec::<(usize, Region, bool)>::new();
+        let mut functions_found = 0;
+        let mut functions_hit = 0;
+        for (function_idx, function) in functions.iter().enumerate() {
+            let mut function_hit = false;
+            let mut first_line = usize::MAX;
+            for counter in function.common_counters() {
+                let hit = counter.id.is_hit(hit_counters);
+                function_hit |= hit;
+                for region in counter.regions {
+                    first_line = first_line.min(region.lines.0);
+                    regions.push((function_idx, region, hit));
+                }
+            }
+            if first_line == usize::MAX {
+                continue;
+            }
+            let name = rustc_demangle::demangle(&function.name).to_string();
+            writeln!(out, "FN:{},{}", first_line, name)?;
+            writeln!(out, "FNDA:{},{}", function_hit as usize, name)?;
+            functions_found += 1;
+            functions_hit += function_hit as usize;
+        }
+        writeln!(out, "FNF:{}", functions_found)?;
+        writeln!(out, "FNH:{}", functions_hit)?;
+
+        let mut branches = BTreeMap::<usize, Vec<(usize, bool)>>::new();
+        for (function_idx, region, hit) in regions.iter() {
+            branches.entry(region.lines.0).or_default().push((*function_idx, *hit));
+        }
+        let mut branches_found = 0;
+        let mut branches_hit = 0;
+        for (line, line_branches) in branches.iter() {
+            if line_branches.len() < 2 {
+                continue;
+            }
+            let any_hit = line_branches.iter().any(|(_, hit)| *hit);
+            for (branch_idx, (block, hit)) in line_branches.iter().enumerate() {
+                let taken = if !any_hit {
+                    "-"
+                } else if *hit {
+                    "1"
+                } else {
+                    "0"
+                };
+                writeln!(out, "BRDA:{},{},{},{}", line, block, branch_idx, taken)?;
+                branches_found += 1;
+                branches_hit += *hit as usize;
+            }
+        }
+        writeln!(out, "BRF:{}", branches_found)?;
+        writeln!(out, "BRH:{}", branches_hit)?;
+
+        let mut lines_found = 0;
+        let mut lines_hit = 0;
+        for (line, hit) in line_hits(regions.iter().map(|(_, region, hit)| (region, *hit))) {
+            writeln!(out, "DA:{},{}", line, hit as usize)?;
+            lines_found += 1;
+            lines_hit += hit as usize;
+        }
+        writeln!(out, "LF:{}", lines_found)?;
+        writeln!(out, "LH:{}", lines_hit)?;
+        writeln!(out, "end_of_record")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(lines: (usize, usize), cols: (usize, usize)) -> Region {
+        Region { lines, cols }
+    }
+
+    #[test]
+    fn line_with_a_missed_branch_is_hit_if_its_wrapping_region_is() {
+        // fn f(x: bool) {
+        //     a(); if x { b(); }
+        // }
+        let body = region((1, 3), (17, 2));
+        let branch = region((2, 2), (15, 23));
+        let hits = line_hits([(&body, true), (&branch, false)]);
+        assert_eq!(hits, BTreeMap::from([(1, true), (2, true), (3, true)]));
+    }
+
+    #[test]
+    fn line_is_hit_if_a_region_starting_on_it_is_hit() {
+        let body = region((1, 3), (17, 2));
+        let branch = region((2, 2), (15, 23));
+        let hits = line_hits([(&body, false), (&branch, true)]);
+        assert_eq!(hits, BTreeMap::from([(1, false), (2, true), (3, false)]));
+    }
+
+    #[test]
+    fn innermost_wrapping_region_is_used() {
+        let body = region((1, 6), (1, 2));
+        let branch = region((2, 5), (10, 6));
+        let hits = line_hits([(&body, true), (&branch, false)]);
+        assert!(!hits[&3]);
+        assert!(hits[&6]);
+    }
+}
@@ -1,10 +1,49 @@
 pub mod args;
+/// Cobertura XML coverage reports
+pub mod cobertura;
+/// loading the files saved by fuzzcheck for a fuzz test
+pub mod data;
+/// decoders turning the inputs of the corpus into readable text
+pub mod decode;
+/// comparison of the coverage of two runs
+pub mod diff;
+/// discovery of the crate, the workspace and the fuzz test to view
+pub mod discover;
+pub mod error;
 /// module containing definitions from the main fuzzcheck crate
 pub mod fuzzcheck;
+/// grouping of the instantiations of generic functions
+pub mod group;
+/// syntax highlighting of the source files
+pub mod highlight;
+/// static HTML coverage reports
+pub mod html;
+/// indexes over the data of a run
+pub mod index;
+/// LCOV tracefiles
+pub mod lcov;
+/// coverage of the lines changed by a git diff
+pub mod patch;
+/// coverage summaries printed by the summary command
+pub mod report;
+/// search of functions by name
+pub mod search;
+/// the source files of the instrumented code
+pub mod sources;
+/// terminal user interface
+#[cfg(feature = "tui")]
+pub mod tui;
+/// detection of the changes made by fuzzcheck while it is running
+pub mod watch;
 
-use fuzzcheck::{CommonCounter, CommonCounterWithSingleRegion, CounterId};
+use fuzzcheck::{CommonCounter, CommonCounterWithSingleRegion, CounterId, Function};
+use highlight::{Highlighting, TokenClass};
+#[cfg(feature = "server")]
 use rocket::form::FromFormField;
 use serde::{Deserialize, Serialize};
+use sources::SourceFiles;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
 
 use crate::fuzzcheck::Region;
 
@@ -20,13 +59,17 @@ pub enum CodeSpanKind {
         status: CoverageStatus,
     },
 }
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CoverageStatus {
     Hit,
     NotHit,
     Best,
     Unique,
     Unknown,
+    /// hit by the new run of a diff, but not by the base run
+    NewlyHit,
+    /// hit by the base run of a diff, but not by the new run
+    NoLongerHit,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -39,6 +82,9 @@ pub struct CodeLine {
 pub struct CodeSpan {
     pub text: String,
     pub kind: CodeSpanKind,
+    /// the Rust token that the text belongs to, if it is colored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<TokenClass>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -49,42 +95,128 @@ pub struct FunctionCoverage {
     pub counter_ids: Vec<usize>,
 }
 
+/// The coverage of a whole source file, in which the code outside of the
+/// functions is untracked.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileCoverage {
+    pub file: String,
+    /// every line of the file, starting from 1
+    pub lines: Vec<CodeLine>,
+    /// the functions shown in the file, sorted by their first line
+    pub functions: Vec<FunctionLines>,
+    pub counter_ids: Vec<usize>,
+}
+
+/// The lines spanned by the regions of a function.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FunctionLines {
+    pub name: FunctionName,
+    pub first_line: usize,
+    pub last_line: usize,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct FunctionName {
     pub name: String,
     pub demangled_name: String,
 }
 
+impl FunctionName {
+    /// Returns the path of the function: its demangled name without the hash
+    /// added by the compiler.
+    pub fn path(&self) -> String {
+        format!("{:#}", rustc_demangle::demangle(&self.name))
+    }
+}
+
+/// How much of the code of a function or file was reached.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CoverageSummary {
+    pub tracked_counters: usize,
+    pub hit_counters: usize,
+    pub inferred_regions: usize,
+    pub inferred_regions_hit: usize,
+    /// percentage of tracked counters that were hit, which is 100 if there are
+    /// no tracked counters
+    pub percentage: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FunctionSummary {
+    pub name: FunctionName,
+    pub summary: CoverageSummary,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileSummary {
+    pub file: String,
+    pub summary: CoverageSummary,
+    pub functions: Vec<FunctionSummary>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum InputFilter {
     All,
     Input(usize),
 }
-#[derive(Clone, Debug, Serialize, Deserialize, FromFormField)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(FromFormField))]
 pub enum FunctionFilter {
     Exclude0PercentCoverage,
     Exclude100PercentCoverage,
 }
-#[derive(Clone, Debug, Serialize, Deserialize, FromFormField)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(FromFormField))]
 pub enum CoverageKindFilter {
     All,
     LeastComplex,
     Unique,
 }
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(FromFormField))]
+pub enum SummarySort {
+    Name,
+    Coverage,
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InputInfo {
     pub pool_idx: usize,
     pub hash: String,
+    /// whether the input is binary data rather than text
+    pub binary: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TargetInfo {
+    /// name of the fuzz test
+    pub name: String,
+    /// whether the data of the fuzz test was already loaded
+    pub loaded: bool,
+    /// whether the fuzz test is used by the requests that don't specify one
+    pub selected: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunInfo {
+    /// index of the run, from the oldest to the most recent
+    pub index: usize,
+    /// name of the stats folder of the run
+    pub name: String,
+    pub selected: bool,
 }
+#[cfg(feature = "server")]
 impl<'v> FromFormField<'v> for InputFilter {
     fn from_value(field: rocket::form::ValueField<'v>) -> rocket::form::Result<'v, Self> {
         match field.value {
             "all" => Ok(InputFilter::All),
-            s => {
-                let idx = s.parse::<usize>().unwrap();
-                Ok(InputFilter::Input(idx))
-            }
+            s => match s.parse::<usize>() {
+                Ok(idx) => Ok(InputFilter::Input(idx)),
+                Err(e) => Err(rocket::form::Error::validation(format!(
+                    "input filter should be 'all' or the index of an input: {}",
+                    e
+                )))?,
+            },
         }
     }
 }
@@ -98,111 +230,470 @@ impl InputFilter {
 }
 
 impl fuzzcheck::CoverageMap {
-    pub fn functions(&self) -> Vec<FunctionCoverage> {
-        let mut code_blocks = self.functions.iter().map(|f| f.coverage()).collect::<Vec<_>>();
-        code_blocks.sort_by(|x, y| ((&x.file, x.lines[0].lineno)).cmp(&(&y.file, y.lines[0].lineno)));
-        code_blocks
+    /// Reads the source files of all the functions of the coverage map.
+    pub fn source_files(&self) -> error::Result<SourceFiles> {
+        SourceFiles::read(self.functions.iter().map(|f| f.file.as_path()))
+    }
+
+    pub fn functions(&self, sources: &SourceFiles) -> error::Result<Vec<FunctionCoverage>> {
+        let mut code_blocks = self
+            .functions
+            .iter()
+            .map(|f| f.coverage(sources))
+            .collect::<error::Result<Vec<_>>>()?;
+        code_blocks.sort_by(|x, y| {
+            (&x.file, x.lines.first().map(|l| l.lineno)).cmp(&(&y.file, y.lines.first().map(|l| l.lineno)))
+        });
+        Ok(code_blocks)
+    }
+
+    /// Returns the coverage summary of every file and of the functions it
+    /// contains, sorted by file and function name.
+    pub fn summaries(&self, hit_counters: &HashSet<usize>) -> Vec<FileSummary> {
+        let mut files = BTreeMap::<String, Vec<FunctionSummary>>::new();
+        for function in self.functions.iter() {
+            files
+                .entry(format!("{}", function.file.display()))
+                .or_default()
+                .push(FunctionSummary {
+                    name: function.function_name(),
+                    summary: function.summary(hit_counters),
+                });
+        }
+        files
+            .into_iter()
+            .map(|(file, mut functions)| {
+                functions.sort_by(|x, y| x.name.demangled_name.cmp(&y.name.demangled_name));
+                let mut summary = CoverageSummary::default();
+                for function in functions.iter() {
+                    summary.add(&function.summary);
+                }
+                FileSummary {
+                    file,
+                    summary,
+                    functions,
+                }
+            })
+            .collect()
+    }
+}
+
+impl FunctionCoverage {
+    /// Returns the number of counters of the function that were hit and the
+    /// total number of counters of the function.
+    pub fn counters_hit(&self, hit_counters: &HashSet<usize>) -> (usize, usize) {
+        let hit = self.counter_ids.iter().filter(|id| hit_counters.contains(id)).count();
+        (hit, self.counter_ids.len())
+    }
+
+    /// Sets the status of every tracked and inferred span to `Hit` or `NotHit`
+    /// depending on whether its counters are in `hit_counters`.
+    pub fn set_hit_statuses(&mut self, hit_counters: &HashSet<usize>) {
+        set_hit_statuses(&mut self.lines, hit_counters);
+    }
+}
+
+impl FileCoverage {
+    /// Renders the whole file with the regions of the given functions, which
+    /// must all be located in `file`. The regions of a function enclosing
+    /// another one, such as a closure, are split around the inner function.
+    /// The regions of a function overlapping another one without enclosing it
+    /// are skipped, so each generic function should be given once, for example
+    /// through the representative of its [`FunctionGroup`](group::FunctionGroup).
+    pub fn new(file: &Path, functions: &[&Function], sources: &SourceFiles) -> error::Result<Self> {
+        let source = sources.get(file)?;
+        let lines = source.lines().collect::<Box<[_]>>();
+
+        let mut function_lines = vec![];
+        // function index -> start and end of the code spanned by its regions
+        let mut function_spans = HashMap::new();
+        let mut counters = vec![];
+        let mut counter_ids = vec![];
+        for (function_idx, function) in functions.iter().enumerate() {
+            let sorted_counters = function.sorted_counters();
+            let Some(first_counter) = sorted_counters.first() else {
+                continue;
+            };
+            let start = (first_counter.region.lines.0, first_counter.region.cols.0);
+            let end = sorted_counters
+                .iter()
+                .map(|c| (c.region.lines.1, c.region.cols.1))
+                .max()
+                .unwrap_or(start);
+            function_spans.insert(function_idx, (start, end));
+            function_lines.push(FunctionLines {
+                name: function.function_name(),
+                first_line: first_counter.region.lines.0,
+                last_line: sorted_counters
+                    .iter()
+                    .map(|c| c.region.lines.1)
+                    .max()
+                    .unwrap_or_default(),
+            });
+            counter_ids.extend(function.counters.iter().map(|c| c.id));
+            counters.extend(sorted_counters.into_iter().map(|counter| (function_idx, counter)));
+        }
+        function_lines.sort_by_key(|f| (f.first_line, f.last_line));
+        counter_ids.sort();
+        counter_ids.dedup();
+        // the code of an inner function is shown with its own regions, so it is
+        // cut out of the regions of the functions enclosing it
+        let mut counters = counters
+            .into_iter()
+            .flat_map(|(function_idx, counter)| {
+                let span = function_spans[&function_idx];
+                let mut inner_spans = function_spans
+                    .values()
+                    .filter(|&&inner| inner != span && span.0 <= inner.0 && inner.1 <= span.1)
+                    .copied()
+                    .collect::<Vec<_>>();
+                inner_spans.sort();
+                split_counter_around(counter, &inner_spans)
+                    .into_iter()
+                    .map(move |counter| (function_idx, counter))
+            })
+            .collect::<Vec<_>>();
+        // the sort is stable, so the regions of the first function come first
+        counters.sort_by_key(|(_, c)| (c.region.lines.0, c.region.cols.0));
+
+        let last_lineno = counters
+            .iter()
+            .map(|(_, c)| c.region.lines.1)
+            .max()
+            .unwrap_or_default()
+            .max(lines.len());
+        if last_lineno == 0 {
+            return Ok(FileCoverage {
+                file: format!("{}", file.display()),
+                lines: vec![],
+                functions: function_lines,
+                counter_ids,
+            });
+        }
+
+        let mut renderer = LineRenderer::new(&lines, sources.highlighting(file), 1, last_lineno);
+        // function index and end of the region ending last so far
+        let mut last_end: Option<(usize, (usize, usize))> = None;
+        for (function_idx, counter) in counters {
+            let start = (counter.region.lines.0, counter.region.cols.0);
+            if let Some((last_function_idx, end)) = last_end {
+                if last_function_idx != function_idx && start < end {
+                    continue;
+                }
+            }
+            let end = (counter.region.lines.1, counter.region.cols.1);
+            if last_end.is_none_or(|(_, last_end)| end > last_end) {
+                last_end = Some((function_idx, end));
+            }
+            renderer.push_counter(counter);
+        }
+        // the code after the last function
+        renderer.push_untracked_until(&Region {
+            lines: (last_lineno, last_lineno),
+            cols: (usize::MAX, usize::MAX),
+        });
+
+        Ok(FileCoverage {
+            file: format!("{}", file.display()),
+            lines: renderer.code_lines,
+            functions: function_lines,
+            counter_ids,
+        })
+    }
+
+    /// Sets the status of every tracked and inferred span to `Hit` or `NotHit`
+    /// depending on whether its counters are in `hit_counters`.
+    pub fn set_hit_statuses(&mut self, hit_counters: &HashSet<usize>) {
+        set_hit_statuses(&mut self.lines, hit_counters);
+    }
+}
+
+fn set_hit_statuses(lines: &mut [CodeLine], hit_counters: &HashSet<usize>) {
+    let status = |hit: bool| {
+        if hit {
+            CoverageStatus::Hit
+        } else {
+            CoverageStatus::NotHit
+        }
+    };
+    for line in lines.iter_mut() {
+        for span in line.spans.iter_mut() {
+            match &mut span.kind {
+                CodeSpanKind::Untracked => {}
+                CodeSpanKind::Inferred {
+                    inferred_from,
+                    status: s,
+                } => {
+                    *s = status(inferred_from.iter().any(|id| hit_counters.contains(id)));
+                }
+                CodeSpanKind::Tracked { id, status: s } => {
+                    *s = status(hit_counters.contains(id));
+                }
+            }
+        }
+    }
+}
+
+impl CoverageStatus {
+    pub fn is_hit(&self) -> bool {
+        matches!(
+            self,
+            CoverageStatus::Hit | CoverageStatus::Best | CoverageStatus::Unique | CoverageStatus::NewlyHit
+        )
+    }
+}
+
+impl CoverageSummary {
+    pub fn add(&mut self, other: &CoverageSummary) {
+        self.tracked_counters += other.tracked_counters;
+        self.hit_counters += other.hit_counters;
+        self.inferred_regions += other.inferred_regions;
+        self.inferred_regions_hit += other.inferred_regions_hit;
+        self.update_percentage();
+    }
+    fn update_percentage(&mut self) {
+        self.percentage = if self.tracked_counters == 0 {
+            100.0
+        } else {
+            self.hit_counters as f64 * 100.0 / self.tracked_counters as f64
+        };
+    }
+}
+
+impl FileSummary {
+    /// Sorts the files, and the functions within each file, in the given order.
+    /// Files and functions with the lowest coverage come first when sorting by
+    /// coverage.
+    pub fn sort(files: &mut [FileSummary], sort: &SummarySort) {
+        match sort {
+            SummarySort::Name => {
+                files.sort_by(|x, y| x.file.cmp(&y.file));
+                for file in files.iter_mut() {
+                    file.functions
+                        .sort_by(|x, y| x.name.demangled_name.cmp(&y.name.demangled_name));
+                }
+            }
+            SummarySort::Coverage => {
+                files.sort_by(|x, y| x.summary.percentage.total_cmp(&y.summary.percentage));
+                for file in files.iter_mut() {
+                    file.functions
+                        .sort_by(|x, y| x.summary.percentage.total_cmp(&y.summary.percentage));
+                }
+            }
+        }
+    }
+}
+
+impl CounterId {
+    /// Returns whether the counter is hit given the set of physical counters
+    /// that were hit. An inferred counter is hit if any of the counters it is
+    /// inferred from is hit.
+    pub fn is_hit(&self, hit_counters: &HashSet<usize>) -> bool {
+        match self {
+            CounterId::Physical { id } => hit_counters.contains(id),
+            CounterId::Inferred { from } => from.iter().any(|id| hit_counters.contains(id)),
+        }
     }
 }
 
 impl fuzzcheck::Function {
-    pub fn coverage(&self) -> FunctionCoverage {
-        let path = &self.file;
+    /// Returns both the physical and inferred counters of the function.
+    pub fn common_counters(&self) -> Vec<CommonCounter> {
+        self.counters
+            .clone()
+            .into_iter()
+            .map(|c| CommonCounter {
+                id: CounterId::Physical { id: c.id },
+                regions: c.regions,
+            })
+            .chain(self.inferred_counters.clone().into_iter().map(|c| CommonCounter {
+                id: CounterId::Inferred {
+                    from: c.from_counter_ids,
+                },
+                regions: c.regions,
+            }))
+            .collect()
+    }
 
-        let name = FunctionName {
+    pub fn function_name(&self) -> FunctionName {
+        FunctionName {
             name: self.name.clone(),
             demangled_name: rustc_demangle::demangle(&self.name).to_string(),
+        }
+    }
+
+    /// Returns how many counters and inferred regions of the function are hit
+    /// given the set of physical counters that were hit.
+    pub fn summary(&self, hit_counters: &HashSet<usize>) -> CoverageSummary {
+        let mut summary = CoverageSummary {
+            tracked_counters: self.counters.len(),
+            hit_counters: self.counters.iter().filter(|c| hit_counters.contains(&c.id)).count(),
+            ..CoverageSummary::default()
         };
-        let file = std::fs::read_to_string(&path).unwrap();
+        for inferred_counter in self.inferred_counters.iter() {
+            let nbr_regions = inferred_counter.regions.len();
+            summary.inferred_regions += nbr_regions;
+            if inferred_counter
+                .from_counter_ids
+                .iter()
+                .any(|id| hit_counters.contains(id))
+            {
+                summary.inferred_regions_hit += nbr_regions;
+            }
+        }
+        summary.update_percentage();
+        summary
+    }
+
+    pub fn coverage(&self, sources: &SourceFiles) -> error::Result<FunctionCoverage> {
+        let name = self.function_name();
+        let file = sources.get(&self.file)?;
         let lines = file.lines().collect::<Box<[_]>>();
-        let sorted_counters = {
-            let common_counters = self
-                .counters
-                .clone()
-                .into_iter()
-                .map(|c| CommonCounter {
-                    id: CounterId::Physical { id: c.id },
-                    regions: c.regions,
-                })
-                .chain(self.inferred_counters.clone().into_iter().map(|c| CommonCounter {
-                    id: CounterId::Inferred {
-                        from: c.from_counter_ids,
-                    },
-                    regions: c.regions,
-                }))
-                .collect::<Vec<_>>();
-            let mut counters: Vec<CommonCounterWithSingleRegion> =
-                common_counters.into_iter().flat_map(split_counter_by_region).collect();
-            counters
-                .sort_by(|c1, c2| (c1.region.lines.0, c1.region.cols.0).cmp(&(c2.region.lines.0, c2.region.cols.0)));
-            counters
-        };
+        let sorted_counters = self.sorted_counters();
 
         if sorted_counters.is_empty() {
-            return FunctionCoverage {
+            return Ok(FunctionCoverage {
                 name,
                 file: format!("no counters for {} in {}", self.name, self.file.display()),
                 lines: vec![],
                 counter_ids: vec![],
-            };
+            });
         }
         let fst_lineno = sorted_counters[0].region.lines.0.saturating_sub(1);
-        let last_lineno = sorted_counters.last().unwrap().region.lines.1;
+        // the region starting last is not the one ending last if it is nested in another one
+        let last_lineno = sorted_counters
+            .iter()
+            .map(|c| c.region.lines.1)
+            .max()
+            .unwrap_or_default();
 
-        let mut code_lines = (fst_lineno..=last_lineno)
-            .map(|lineno| CodeLine { lineno, spans: vec![] })
-            .collect::<Vec<_>>();
-
-        let mut last_region = Region {
-            lines: (fst_lineno, fst_lineno),
-            cols: (0, 0),
-        };
-        for whole_counter in sorted_counters {
-            let counters = split_counter_by_line(whole_counter.clone());
-            for counter in counters {
-                let in_between = region_between_regions(&last_region, &counter.region);
-                if !region_is_empty(&in_between) {
-                    let in_between = split_region_by_line(&in_between);
-                    for r in in_between {
-                        code_lines[r.lines.0 - fst_lineno].spans.push(CodeSpan {
-                            text: string_in_region(&lines, &r),
-                            kind: CodeSpanKind::Untracked,
-                        })
-                    }
-                }
-                let region = split_region_by_line(&counter.region);
-                for r in region {
-                    let text = string_in_region(&lines, &r);
-                    if text.is_empty() && whole_counter.region.lines.0 == whole_counter.region.lines.1 {
-                        code_lines[r.lines.0 - fst_lineno].spans.push(CodeSpan {
-                            text: " ".to_owned(),
-                            kind: CodeSpanKind::Untracked,
-                        });
-                        code_lines[r.lines.0 - fst_lineno].spans.push(CodeSpan {
-                            text: " ⦿ ".to_owned(),
-                            kind: unknown_code_span_kind_from_counter_id(counter.id.clone()),
-                        });
-                    } else {
-                        code_lines[r.lines.0 - fst_lineno].spans.push(CodeSpan {
-                            text,
-                            kind: unknown_code_span_kind_from_counter_id(counter.id.clone()),
-                        });
-                    }
-                }
-                last_region = counter.region;
-            }
+        let mut renderer = LineRenderer::new(&lines, sources.highlighting(&self.file), fst_lineno, last_lineno);
+        for counter in sorted_counters {
+            renderer.push_counter(counter);
         }
 
         let mut counter_ids = self.counters.iter().map(|c| c.id).collect::<Vec<_>>();
         counter_ids.sort();
 
-        FunctionCoverage {
+        Ok(FunctionCoverage {
             name,
             file: format!("{}", self.file.display()),
-            lines: code_lines,
+            lines: renderer.code_lines,
             counter_ids,
+        })
+    }
+
+    /// Returns the counters of the function with one region each, sorted by
+    /// the start of their region.
+    fn sorted_counters(&self) -> Vec<CommonCounterWithSingleRegion> {
+        let mut counters: Vec<CommonCounterWithSingleRegion> = self
+            .common_counters()
+            .into_iter()
+            .flat_map(split_counter_by_region)
+            .collect();
+        counters.sort_by_key(|c| (c.region.lines.0, c.region.cols.0));
+        counters
+    }
+}
+
+/// Builds the lines of code shown for a sequence of counters, with the text
+/// between their regions as untracked spans.
+struct LineRenderer<'a> {
+    lines: &'a [&'a str],
+    highlighting: Option<&'a Highlighting>,
+    fst_lineno: usize,
+    code_lines: Vec<CodeLine>,
+    last_region: Region,
+}
+
+impl<'a> LineRenderer<'a> {
+    fn new(
+        lines: &'a [&'a str],
+        highlighting: Option<&'a Highlighting>,
+        fst_lineno: usize,
+        last_lineno: usize,
+    ) -> Self {
+        LineRenderer {
+            lines,
+            highlighting,
+            fst_lineno,
+            code_lines: (fst_lineno..=last_lineno)
+                .map(|lineno| CodeLine { lineno, spans: vec![] })
+                .collect(),
+            last_region: Region {
+                lines: (fst_lineno, fst_lineno),
+                cols: (0, 0),
+            },
         }
     }
+
+    /// Pushes the text between the previous region and `region` as untracked.
+    fn push_untracked_until(&mut self, region: &Region) {
+        let in_between = region_between_regions(&self.last_region, region);
+        if !region_is_empty(&in_between) {
+            for r in split_region_by_line(&in_between) {
+                let text = string_in_region(self.lines, &r);
+                self.code_lines[r.lines.0 - self.fst_lineno]
+                    .spans
+                    .extend(highlighted_spans(self.highlighting, &r, text, CodeSpanKind::Untracked));
+            }
+        }
+    }
+
+    /// Pushes the region of the counter, after the untracked text before it.
+    /// The counters must be pushed in the order of their regions.
+    fn push_counter(&mut self, whole_counter: CommonCounterWithSingleRegion) {
+        let is_single_line = whole_counter.region.lines.0 == whole_counter.region.lines.1;
+        for counter in split_counter_by_line(whole_counter) {
+            self.push_untracked_until(&counter.region);
+            for r in split_region_by_line(&counter.region) {
+                let text = string_in_region(self.lines, &r);
+                let spans = &mut self.code_lines[r.lines.0 - self.fst_lineno].spans;
+                if text.is_empty() && is_single_line {
+                    spans.push(CodeSpan {
+                        text: " ".to_owned(),
+                        kind: CodeSpanKind::Untracked,
+                        token: None,
+                    });
+                    spans.push(CodeSpan {
+                        text: " ⦿ ".to_owned(),
+                        kind: unknown_code_span_kind_from_counter_id(counter.id.clone()),
+                        token: None,
+                    });
+                } else {
+                    let kind = unknown_code_span_kind_from_counter_id(counter.id.clone());
+                    spans.extend(highlighted_spans(self.highlighting, &r, text, kind));
+                }
+            }
+            self.last_region = counter.region;
+        }
+    }
+}
+
+/// Splits the text of a region spanning a single line at the boundaries of the
+/// Rust tokens, each piece keeping the kind of the region.
+fn highlighted_spans(
+    highlighting: Option<&Highlighting>,
+    region: &Region,
+    text: String,
+    kind: CodeSpanKind,
+) -> Vec<CodeSpan> {
+    let Some(highlighting) = highlighting else {
+        return vec![CodeSpan {
+            text,
+            kind,
+            token: None,
+        }];
+    };
+    highlighting
+        .split(region.lines.0.saturating_sub(1), region.cols.0.saturating_sub(1), &text)
+        .into_iter()
+        .map(|(text, token)| CodeSpan {
+            text,
+            kind: kind.clone(),
+            token,
+        })
+        .collect()
 }
 
 fn unknown_code_span_kind_from_counter_id(id: CounterId) -> CodeSpanKind {
@@ -228,6 +719,8 @@ fn string_in_region(lines: &[&str], region: &Region) -> String {
     if line_start >= lines.len() {
         return format!("error: line_start ( {} ) > lines.len()", line_start);
     }
+    // the region may extend past the end of a file modified since the run
+    let line_end = line_end.min(lines.len() - 1);
     let col_start = region.cols.0.saturating_sub(1);
     let col_end = region.cols.1.saturating_sub(1);
 
@@ -245,16 +738,16 @@ fn string_in_region(lines: &[&str], region: &Region) -> String {
         .unwrap_or(last_line.len());
     let mut span = String::new();
     if line_end == line_start {
-        span.push_str(&fst_line[start..end]);
+        span.push_str(fst_line.get(start..end).unwrap_or_default());
     } else {
         // add the first line
         span.push_str(&fst_line[start..]);
         // add all the lines in between
-        for i in line_start + 1..line_end {
-            span.push_str("\n");
-            span.push_str(lines[i]);
+        for line in &lines[line_start + 1..line_end] {
+            span.push('\n');
+            span.push_str(line);
         }
-        span.push_str("\n");
+        span.push('\n');
         // add last line
         span.push_str(&last_line[..end]);
     }
@@ -278,6 +771,45 @@ fn split_counter_by_region(c: CommonCounter) -> Vec<CommonCounterWithSingleRegio
         })
         .collect()
 }
+
+/// The line and column of a character of a source file, both starting from 1.
+type Position = (usize, usize);
+
+/// Splits the region of the counter around the given spans of code, which are
+/// sorted by their start. The parts of the region inside the spans are removed.
+fn split_counter_around(
+    c: CommonCounterWithSingleRegion,
+    spans: &[(Position, Position)],
+) -> Vec<CommonCounterWithSingleRegion> {
+    let start = (c.region.lines.0, c.region.cols.0);
+    let end = (c.region.lines.1, c.region.cols.1);
+    if !spans
+        .iter()
+        .any(|&(span_start, span_end)| span_start < end && start < span_end)
+    {
+        return vec![c];
+    }
+    let piece = |start: Position, end: Position| CommonCounterWithSingleRegion {
+        id: c.id.clone(),
+        region: Region {
+            lines: (start.0, end.0),
+            cols: (start.1, end.1),
+        },
+    };
+    let mut pieces = vec![];
+    let mut piece_start = start;
+    for &(span_start, span_end) in spans {
+        if piece_start < span_start.min(end) {
+            pieces.push(piece(piece_start, span_start.min(end)));
+        }
+        piece_start = piece_start.max(span_end);
+    }
+    if piece_start < end {
+        pieces.push(piece(piece_start, end));
+    }
+    pieces
+}
+
 fn split_counter_by_line(c: CommonCounterWithSingleRegion) -> Vec<CommonCounterWithSingleRegion> {
     let mut counters = vec![];
     for l in c.region.lines.0..=c.region.lines.1 {
@@ -311,3 +843,103 @@ fn split_region_by_line(region: &Region) -> Vec<Region> {
     }
     regions
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use fuzzcheck::Counter;
+
+    fn function(name: &str, id: usize, lines: (usize, usize), cols: (usize, usize)) -> Function {
+        Function {
+            name: name.to_owned(),
+            file: PathBuf::from("lib.rs"),
+            counters: vec![Counter {
+                id,
+                regions: vec![Region { lines, cols }],
+            }],
+            inferred_counters: vec![],
+        }
+    }
+
+    /// Returns the text of each line, split into the pieces of each counter.
+    fn counter_pieces(coverage: &FileCoverage) -> Vec<Vec<(String, Option<usize>)>> {
+        coverage
+            .lines
+            .iter()
+            .map(|line| {
+                let mut pieces = Vec::<(String, Option<usize>)>::new();
+                for span in line.spans.iter().filter(|span| !span.text.is_empty()) {
+                    let id = match span.kind {
+                        CodeSpanKind::Tracked { id, .. } => Some(id),
+                        _ => None,
+                    };
+                    match pieces.last_mut() {
+                        Some((text, last_id)) if *last_id == id => text.push_str(&span.text),
+                        _ => pieces.push((span.text.clone(), id)),
+                    }
+                }
+                pieces
+            })
+            .collect()
+    }
+
+    #[test]
+    fn function_ends_with_the_region_ending_last() {
+        let source = "\
+fn f(x: bool) {
+    if x {
+        a();
+    }
+    b();
+}
+";
+        let sources = SourceFiles::new(HashMap::from([(PathBuf::from("lib.rs"), source.to_owned())]));
+        let mut function = function("f", 0, (1, 6), (1, 2));
+        function.counters.push(Counter {
+            id: 1,
+            regions: vec![Region {
+                lines: (2, 2),
+                cols: (8, 11),
+            }],
+        });
+        let coverage = function.coverage(&sources).unwrap();
+        assert_eq!(coverage.lines.last().map(|line| line.lineno), Some(6));
+    }
+
+    #[test]
+    fn closure_is_shown_inside_its_function() {
+        let source = "\
+fn f(v: &[u8]) -> usize {
+    let g = |x: &u8| {
+        *x as usize
+    };
+    v.iter().map(g).sum()
+}
+";
+        let sources = SourceFiles::new(HashMap::from([(PathBuf::from("lib.rs"), source.to_owned())]));
+        let outer = function("f", 0, (1, 6), (1, 2));
+        let closure = function("f::{closure#0}", 1, (2, 4), (13, 6));
+        let coverage = FileCoverage::new(Path::new("lib.rs"), &[&outer, &closure], &sources).unwrap();
+
+        let piece = |text: &str, id| (text.to_owned(), Some(id));
+        assert_eq!(
+            counter_pieces(&coverage),
+            vec![
+                vec![piece("fn f(v: &[u8]) -> usize {", 0)],
+                vec![piece("    let g = ", 0), piece("|x: &u8| {", 1)],
+                vec![piece("        *x as usize", 1)],
+                vec![piece("    }", 1), piece(";", 0)],
+                vec![piece("    v.iter().map(g).sum()", 0)],
+                vec![piece("}", 0)],
+            ]
+        );
+        let functions = coverage
+            .functions
+            .iter()
+            .map(|f| (f.first_line, f.last_line))
+            .collect::<Vec<_>>();
+        assert_eq!(functions, vec![(1, 6), (2, 4)]);
+    }
+}
@@ -1,338 +1,1279 @@
-#![feature(drain_filter)]
 #[macro_use]
 extern crate rocket;
 
-use fuzzcheck_view::args::CliArguments;
-use fuzzcheck_view::fuzzcheck::{read_input_corpus, CorpusMap, CoverageMap, SerializedUniqCov};
+use fuzzcheck_view::args::{CliArguments, Command};
+use fuzzcheck_view::cobertura::write_cobertura;
+use fuzzcheck_view::data::{find_run, fuzz_tests, run_name, stats_runs, FuzzTestData, RunCoverage, RunSelector};
+use fuzzcheck_view::decode::{is_binary, DecodedInput, InputDecoder};
+use fuzzcheck_view::diff::{CoverageDiff, FileDiff};
+use fuzzcheck_view::error::Error;
+use fuzzcheck_view::fuzzcheck::{CoverageMap, Function, SerializedUniqCov};
+use fuzzcheck_view::group::{group_functions, EquivalentCounters, FunctionGroup};
+use fuzzcheck_view::index::RunIndex;
+use fuzzcheck_view::lcov::write_lcov;
+use fuzzcheck_view::patch::{ChangedLines, PatchCoverage};
+use fuzzcheck_view::report::SummaryReport;
+use fuzzcheck_view::search::{FunctionSearch, SearchMode, SearchResult};
+use fuzzcheck_view::sources::{SourceFiles, SNAPSHOT_FOLDER};
+use fuzzcheck_view::watch::Snapshot;
 use fuzzcheck_view::{
-    CodeSpanKind, CoverageKindFilter, CoverageStatus, FunctionCoverage, FunctionFilter, FunctionName, InputFilter,
-    InputInfo,
+    CodeLine, CodeSpanKind, CoverageKindFilter, CoverageStatus, FileCoverage, FileSummary, FunctionCoverage,
+    FunctionFilter, FunctionName, InputFilter, InputInfo, RunInfo, SummarySort, TargetInfo,
 };
+use rocket::fairing::AdHoc;
+use rocket::form::{self, error::ErrorKind};
+use rocket::http::uri::Origin;
+use rocket::http::{ContentType, Header, Status};
 use rocket::response::content::RawHtml;
+use rocket::response::stream::{Event, EventStream};
+use rocket::response::{self, Redirect, Responder};
 use rocket::serde::json::Json;
-use rocket::{fs::NamedFile, State};
-use std::collections::{HashMap, HashSet};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::{self, error::RecvError};
+use rocket::tokio::task::spawn_blocking;
+use rocket::{Build, Request, Rocket, Shutdown, State};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{BufWriter, Write};
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
+/// maximum number of functions returned by `/search` if no limit is given
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// how often the files written by fuzzcheck are checked for changes with --watch
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// An error returned by the API as a JSON object with a single `error` field
+struct ApiError {
+    status: Status,
+    message: String,
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+#[derive(Serialize)]
+struct ErrorMessage {
+    error: String,
+}
+
+impl From<Error> for ApiError {
+    fn from(error: Error) -> Self {
+        let status = if error.is_not_found() {
+            Status::NotFound
+        } else if matches!(error, Error::InvalidPattern { .. } | Error::InvalidRange { .. }) {
+            Status::BadRequest
+        } else if matches!(error, Error::Decode { .. }) {
+            Status::UnprocessableEntity
+        } else {
+            Status::InternalServerError
+        };
+        ApiError {
+            status,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl ApiError {
+    /// Returns a 400 error giving the reason why the query parameter `name` is
+    /// invalid.
+    fn bad_query(name: &str, errors: form::Errors<'_>) -> Self {
+        let message = errors
+            .iter()
+            .map(|error| match error.kind {
+                ErrorKind::Missing => format!("missing {}", name),
+                _ => format!("invalid {}: {}", name, error),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        ApiError {
+            status: Status::BadRequest,
+            message,
+        }
+    }
+}
+
+/// Returns the value of the query parameter `name`, or a 400 error if it is
+/// missing or invalid. Rocket would otherwise forward the request and answer
+/// 404.
+fn query_param<T>(name: &str, param: form::Result<'_, T>) -> Result<T, ApiError> {
+    param.map_err(|errors| ApiError::bad_query(name, errors))
+}
+
+/// Returns the value of the optional query parameter `name`, `None` if it is
+/// missing, or a 400 error if it is invalid. An `Option` parameter would
+/// silently ignore an invalid value.
+fn optional_query_param<T>(name: &str, param: form::Result<'_, T>) -> Result<Option<T>, ApiError> {
+    match param {
+        Ok(value) => Ok(Some(value)),
+        Err(errors) if errors.iter().all(|error| matches!(error.kind, ErrorKind::Missing)) => Ok(None),
+        Err(errors) => Err(ApiError::bad_query(name, errors)),
+    }
+}
+
+/// Runs `f` on the thread pool for blocking tasks, so that loading a run
+/// doesn't block the requests handled by the same worker.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T, Error> + Send + 'static) -> Result<T, ApiError> {
+    match spawn_blocking(f).await {
+        Ok(result) => Ok(result?),
+        Err(error) => Err(ApiError {
+            status: Status::InternalServerError,
+            message: format!("the loading task failed: {}", error),
+        }),
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        (self.status, Json(ErrorMessage { error: self.message })).respond_to(request)
+    }
+}
+
+#[catch(default)]
+fn default_catcher(status: Status, _request: &Request) -> (Status, Json<ErrorMessage>) {
+    (
+        status,
+        Json(ErrorMessage {
+            error: status.to_string(),
+        }),
+    )
+}
+
+const INDEX_HTML: &[u8] = include_bytes!("resources/index.html");
+
+/// The files served to the browser, embedded in the executable. No other file
+/// is served, whatever the path requested.
+const ASSETS: &[Asset] = &[Asset {
+    path: "index.html",
+    content_type: ContentType::HTML,
+    content: INDEX_HTML,
+}];
+
+struct Asset {
+    /// path of the file relative to the base path of the viewer
+    path: &'static str,
+    content_type: ContentType,
+    content: &'static [u8],
+}
+
+/// The webpage, or a redirection to it
+#[derive(Responder)]
+// a response is only built once per request, its size doesn't matter
+#[allow(clippy::large_enum_variant)]
+enum Page {
+    Html(RawHtml<&'static [u8]>),
+    Redirect(Redirect),
+}
+
+/// The webpage, redirected to the base path followed by a slash so that the
+/// relative URLs of its requests stay under the base path
 #[get("/")]
-fn index() -> RawHtml<&'static [u8]> {
-    let html = include_bytes!("resources/index.html");
-    RawHtml(html.as_slice())
-}
-
-#[get("/functions?<input_filter>&<function_filter>&<coverage_kind_filter>")]
-fn functions(
-    state: &State<ManagedData>,
-    input_filter: InputFilter,
-    function_filter: Vec<FunctionFilter>,
-    coverage_kind_filter: CoverageKindFilter,
-) -> Json<Vec<(String, Vec<FunctionName>)>> {
+fn index(uri: &Origin<'_>) -> Page {
+    let path = uri.path();
+    if !path.ends_with('/') {
+        return Page::Redirect(Redirect::permanent(format!("{}/", path)));
+    }
+    Page::Html(RawHtml(INDEX_HTML))
+}
+
+/// The functions of each file. If `merge_generics` is true, or if it is not
+/// given and --merge-generics was passed, the instantiations of a generic
+/// function are listed once.
+#[get("/functions?<input_filter>&<function_filter>&<coverage_kind_filter>&<merge_generics>&<target>")]
+async fn functions(
+    server: &State<Server>,
+    input_filter: form::Result<'_, InputFilter>,
+    function_filter: form::Result<'_, Vec<FunctionFilter>>,
+    coverage_kind_filter: form::Result<'_, CoverageKindFilter>,
+    merge_generics: Option<bool>,
+    target: Option<&str>,
+) -> ApiResult<Vec<(String, Vec<FunctionName>)>> {
+    let input_filter = query_param("input_filter", input_filter)?;
+    let function_filter = query_param("function_filter", function_filter)?;
+    let coverage_kind_filter = query_param("coverage_kind_filter", coverage_kind_filter)?;
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
+    let merge_generics = merge_generics.unwrap_or(server.args.merge_generics);
+    let functions_per_file = if merge_generics {
+        &state.merged_functions_per_file
+    } else {
+        &state.functions_per_file
+    };
+    if function_filter.is_empty() {
+        return Ok(Json(functions_per_file.clone().into_iter().collect()));
+    }
+    let summaries = filtered_summaries(&state, &input_filter, &function_filter, &coverage_kind_filter)?;
+    let passing = summaries
+        .into_iter()
+        .flat_map(|file| file.functions)
+        .map(|function| function.name.name)
+        .collect::<HashSet<_>>();
+    // the filtered functions are listed in the same order as the unfiltered ones
+    Ok(Json(
+        functions_per_file
+            .iter()
+            .map(|(file, functions)| {
+                let functions = functions
+                    .iter()
+                    .filter(|name| {
+                        if merge_generics {
+                            // a group is listed if any of its instantiations passes the filters
+                            let group = &state.groups[state.group_of[&name.name]];
+                            group.instantiations.iter().any(|name| passing.contains(&name.name))
+                        } else {
+                            passing.contains(&name.name)
+                        }
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (file.clone(), functions)
+            })
+            .filter(|(_, functions)| !functions.is_empty())
+            .collect(),
+    ))
+}
+
+/// The generic functions with more than one instantiation, whose coverage can
+/// be requested separately from `/coverage`
+#[get("/generics?<target>")]
+async fn generics(server: &State<Server>, target: Option<&str>) -> ApiResult<Vec<FunctionGroup>> {
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
+    Ok(Json(
+        state
+            .groups
+            .iter()
+            .filter(|group| group.instantiations.len() > 1)
+            .cloned()
+            .collect(),
+    ))
+}
+
+#[get("/summary?<input_filter>&<function_filter>&<coverage_kind_filter>&<sort>&<target>")]
+async fn summary(
+    server: &State<Server>,
+    input_filter: form::Result<'_, InputFilter>,
+    function_filter: form::Result<'_, Vec<FunctionFilter>>,
+    coverage_kind_filter: form::Result<'_, CoverageKindFilter>,
+    sort: form::Result<'_, SummarySort>,
+    target: Option<&str>,
+) -> ApiResult<Vec<FileSummary>> {
+    let input_filter = query_param("input_filter", input_filter)?;
+    let function_filter = query_param("function_filter", function_filter)?;
+    let coverage_kind_filter = optional_query_param("coverage_kind_filter", coverage_kind_filter)?;
+    let sort = optional_query_param("sort", sort)?;
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
+    let coverage_kind_filter = coverage_kind_filter.unwrap_or(CoverageKindFilter::All);
+    let mut summaries = filtered_summaries(&state, &input_filter, &function_filter, &coverage_kind_filter)?;
+    FileSummary::sort(&mut summaries, &sort.unwrap_or(SummarySort::Name));
+    Ok(Json(summaries))
+}
+
+/// The functions whose demangled name or file matches `query`, the best
+/// matches first, with their coverage. The hash added by the compiler is
+/// ignored unless `strip_hash` is false.
+#[get("/search?<query>&<mode>&<strip_hash>&<input_filter>&<coverage_kind_filter>&<limit>&<target>")]
+#[allow(clippy::too_many_arguments)]
+async fn search(
+    server: &State<Server>,
+    query: &str,
+    mode: form::Result<'_, SearchMode>,
+    strip_hash: Option<bool>,
+    input_filter: form::Result<'_, InputFilter>,
+    coverage_kind_filter: form::Result<'_, CoverageKindFilter>,
+    limit: Option<usize>,
+    target: Option<&str>,
+) -> ApiResult<Vec<SearchResult>> {
+    let mode = optional_query_param("mode", mode)?;
+    let input_filter = optional_query_param("input_filter", input_filter)?;
+    let coverage_kind_filter = optional_query_param("coverage_kind_filter", coverage_kind_filter)?;
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
+    let search = FunctionSearch::new(query, mode.unwrap_or_default(), strip_hash.unwrap_or(true))?;
+    let counters = state.index.filtered_counters(
+        &input_filter.unwrap_or(InputFilter::All),
+        &coverage_kind_filter.unwrap_or(CoverageKindFilter::All),
+    )?;
+    let mut results = search.search(&state.coverage_map.summaries(&counters));
+    results.truncate(limit.unwrap_or(DEFAULT_SEARCH_LIMIT));
+    Ok(Json(results))
+}
+
+/// Returns the coverage summary of every file and function, excluding the
+/// functions that don't pass the function filters.
+///
+/// The summary of a file always covers all of its functions, even those that
+/// were excluded.
+fn filtered_summaries(
+    state: &ManagedData,
+    input_filter: &InputFilter,
+    function_filter: &[FunctionFilter],
+    coverage_kind_filter: &CoverageKindFilter,
+) -> Result<Vec<FileSummary>, Error> {
     let exclude_100 = function_filter
         .iter()
-        .find(|filter| matches!(filter, FunctionFilter::Exclude100PercentCoverage))
-        .is_some();
+        .any(|filter| matches!(filter, FunctionFilter::Exclude100PercentCoverage));
     let exclude_0 = function_filter
         .iter()
-        .find(|filter| matches!(filter, FunctionFilter::Exclude0PercentCoverage))
-        .is_some();
-    if !(exclude_0 || exclude_100) {
-        return Json(state.functions_per_file.clone().into_iter().collect());
+        .any(|filter| matches!(filter, FunctionFilter::Exclude0PercentCoverage));
+    let input_counters = state.index.filtered_counters(input_filter, coverage_kind_filter)?;
+
+    let mut summaries = state.coverage_map.summaries(&input_counters);
+    for file in summaries.iter_mut() {
+        file.functions.retain(|function| {
+            let summary = &function.summary;
+            let any_counter_hit = summary.hit_counters > 0;
+            let all_counters_hit = summary.hit_counters == summary.tracked_counters;
+            !((exclude_0 && !any_counter_hit) || (exclude_100 && all_counters_hit))
+        });
     }
-    let input_counters = match input_filter {
-        InputFilter::All => HashSet::<usize>::from_iter(state.simplest_cov.all_hit_counters.iter().copied()),
+    summaries.retain(|file| !file.functions.is_empty());
+    Ok(summaries)
+}
+
+/// The coverage of a function. If `merge_generics` is true, or if it is not
+/// given and --merge-generics was passed, a span of a generic function is hit
+/// if it is hit in any of its instantiations.
+#[get("/coverage?<input_filter>&<function>&<coverage_kind_filter>&<merge_generics>&<target>")]
+async fn coverage(
+    server: &State<Server>,
+    input_filter: form::Result<'_, InputFilter>,
+    function: String,
+    coverage_kind_filter: form::Result<'_, CoverageKindFilter>,
+    merge_generics: Option<bool>,
+    target: Option<&str>,
+) -> ApiResult<FunctionCoverage> {
+    let input_filter = query_param("input_filter", input_filter)?;
+    let coverage_kind_filter = optional_query_param("coverage_kind_filter", coverage_kind_filter)?;
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
+    let merge_generics = merge_generics.unwrap_or(server.args.merge_generics);
+    let group = state
+        .group_of
+        .get(&function)
+        .map(|&group_idx| &state.groups[group_idx])
+        .ok_or_else(|| Error::UnknownFunction { name: function.clone() })?;
+    match input_filter {
+        InputFilter::All => {
+            let function_coverage = if merge_generics {
+                &state.merged_coverage[&group.representative.name]
+            } else {
+                &state.function_coverage[&function]
+            };
+            Ok(Json(function_coverage.clone()))
+        }
         InputFilter::Input(input_idx) => {
-            let all_input_counters = HashSet::<usize>::from_iter(
-                state
-                    .simplest_cov
-                    .counters_for_input
-                    .iter()
-                    .find(|(idx, _)| *idx == input_idx)
-                    .unwrap()
-                    .1
-                    .iter()
-                    .copied(),
-            );
-            match coverage_kind_filter {
-                CoverageKindFilter::All => all_input_counters,
-                CoverageKindFilter::LeastComplex => {
-                    let mut input_counters = all_input_counters;
-                    for (counter_idx, best_input_idx) in state.simplest_cov.best_for_counter.iter() {
-                        // 1. this is the right input
-                        if input_counters.contains(counter_idx) {
-                            // 2. but it is not the least complex
-                            if input_idx != *best_input_idx {
-                                input_counters.remove(counter_idx);
-                            }
-                        } else {
-                            input_counters.remove(counter_idx);
-                        }
-                    }
-                    input_counters
-                }
-                CoverageKindFilter::Unique => todo!(),
+            let functions = if merge_generics {
+                group.functions(&state.coverage_map)
+            } else {
+                group.functions_named(&state.coverage_map, &function)
+            };
+            // the coverage is shown with the counter ids of the first function
+            let equivalents = EquivalentCounters::new(&functions);
+            let coverage_kind_filter = coverage_kind_filter.unwrap_or(CoverageKindFilter::All);
+            let counters = equivalents.merge(&state.index.filtered_counters(&input_filter, &coverage_kind_filter)?);
+            let unique_counters = equivalents.merge(&state.index.unique_counters_for_input(input_idx));
+            let mut block = functions[0].coverage(&state.sources)?;
+            if merge_generics {
+                block.name = group.merged_name();
             }
+            set_input_statuses(&mut block.lines, &counters, &unique_counters, |id| {
+                equivalents.any(id, |id| state.index.is_best_input_for_counter(input_idx, id))
+            });
+            Ok(Json(block))
         }
-    };
+    }
+}
 
-    let mut functions_per_file = state.functions_per_file.clone();
-    let mut files_to_remove = vec![];
-    for (file, function_names) in functions_per_file.iter_mut() {
-        function_names.drain_filter(|function_name| {
-            let function = &state.function_coverage[&function_name.name];
-            let mut any_counter_hit = false;
-            let mut all_counters_hit = true;
-            for counter_id in function.counter_ids.iter() {
-                if input_counters.contains(counter_id) {
-                    any_counter_hit = true
-                } else {
-                    all_counters_hit = false
-                }
-            }
-            (exclude_0 && !any_counter_hit) || (exclude_100 && all_counters_hit)
-        });
-        if function_names.is_empty() {
-            files_to_remove.push(file.clone());
+/// The coverage of a whole file, with each generic function shown once with
+/// the merged coverage of its instantiations.
+#[get("/coverage/file?<input_filter>&<file>&<coverage_kind_filter>&<target>")]
+async fn file_coverage(
+    server: &State<Server>,
+    input_filter: form::Result<'_, InputFilter>,
+    file: String,
+    coverage_kind_filter: form::Result<'_, CoverageKindFilter>,
+    target: Option<&str>,
+) -> ApiResult<FileCoverage> {
+    let input_filter = query_param("input_filter", input_filter)?;
+    let coverage_kind_filter = optional_query_param("coverage_kind_filter", coverage_kind_filter)?;
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
+    let groups = state
+        .groups
+        .iter()
+        .filter(|group| group.file == file)
+        .collect::<Vec<_>>();
+    let functions = groups
+        .iter()
+        .map(|group| group.functions(&state.coverage_map))
+        .collect::<Vec<_>>();
+    let Some(path) = functions.iter().flatten().next().map(|function| function.file.clone()) else {
+        return Err(Error::UnknownFile { file }.into());
+    };
+    let equivalents = functions
+        .iter()
+        .map(|functions| EquivalentCounters::new(functions))
+        .collect::<Vec<_>>();
+    let merge = |counters: &HashSet<usize>| {
+        equivalents
+            .iter()
+            .fold(counters.clone(), |merged, equivalents| equivalents.merge(&merged))
+    };
+    let representatives = functions.iter().map(|functions| functions[0]).collect::<Vec<_>>();
+    let mut coverage = FileCoverage::new(&path, &representatives, &state.sources)?;
+    for function in coverage.functions.iter_mut() {
+        if let Some(&group_idx) = state.group_of.get(&function.name.name) {
+            function.name = state.groups[group_idx].merged_name();
         }
     }
-    for file_to_remove in files_to_remove {
-        functions_per_file.remove(&file_to_remove);
+    match input_filter {
+        InputFilter::All => coverage.set_hit_statuses(&merge(&state.index.all_hit_counters)),
+        InputFilter::Input(input_idx) => {
+            let coverage_kind_filter = coverage_kind_filter.unwrap_or(CoverageKindFilter::All);
+            let counters = merge(&state.index.filtered_counters(&input_filter, &coverage_kind_filter)?);
+            let unique_counters = merge(&state.index.unique_counters_for_input(input_idx));
+            set_input_statuses(&mut coverage.lines, &counters, &unique_counters, |id| {
+                equivalents
+                    .iter()
+                    .any(|equivalents| equivalents.any(id, |id| state.index.is_best_input_for_counter(input_idx, id)))
+            });
+        }
     }
-
-    Json(functions_per_file.into_iter().collect())
+    Ok(Json(coverage))
 }
 
-#[get("/coverage?<input_filter>&<function>")]
-fn coverage(state: &State<ManagedData>, input_filter: InputFilter, function: String) -> Json<FunctionCoverage> {
-    match input_filter {
-        InputFilter::All => {
-            let function_coverage = state.function_coverage.get(&function).unwrap();
-            Json(function_coverage.clone())
-        }
-        InputFilter::Input(input_idx) => {
-            let counters = &state
-                .simplest_cov
-                .counters_for_input
-                .iter()
-                .find(|x| x.0 == input_idx)
-                .unwrap()
-                .1;
-            let counters = HashSet::<usize>::from_iter(counters.iter().copied());
-            let function = state
-                .coverage_map
-                .functions
-                .iter()
-                .find(|f| f.name == function)
-                .unwrap();
-            let mut block = function.coverage();
-            for line in block.lines.iter_mut() {
-                for span in line.spans.iter_mut() {
-                    match &mut span.kind {
-                        CodeSpanKind::Untracked => {}
-                        CodeSpanKind::Inferred { inferred_from, status } => {
-                            *status = if counters.iter().any(|hit_id| inferred_from.contains(hit_id)) {
-                                CoverageStatus::Hit
-                            } else {
-                                CoverageStatus::NotHit
-                            }
-                        }
-                        CodeSpanKind::Tracked { id, status } => {
-                            *status = if counters.contains(&id) {
-                                if input_idx
-                                    == state
-                                        .simplest_cov
-                                        .best_for_counter
-                                        .iter()
-                                        .find(|(x, _)| x == id)
-                                        .unwrap()
-                                        .1
-                                {
-                                    CoverageStatus::Best
-                                } else {
-                                    CoverageStatus::Hit
-                                }
-                            } else {
-                                CoverageStatus::NotHit
-                            };
-                        }
+/// Sets the status of the spans for the coverage of a single input, given the
+/// counters it hits and those that only it hits.
+fn set_input_statuses(
+    lines: &mut [CodeLine],
+    counters: &HashSet<usize>,
+    unique_counters: &HashSet<usize>,
+    is_best_input: impl Fn(usize) -> bool,
+) {
+    for line in lines.iter_mut() {
+        for span in line.spans.iter_mut() {
+            match &mut span.kind {
+                CodeSpanKind::Untracked => {}
+                CodeSpanKind::Inferred { inferred_from, status } => {
+                    *status = if inferred_from.iter().any(|id| counters.contains(id)) {
+                        CoverageStatus::Hit
+                    } else {
+                        CoverageStatus::NotHit
                     }
                 }
+                CodeSpanKind::Tracked { id, status } => {
+                    *status = if !counters.contains(id) {
+                        CoverageStatus::NotHit
+                    } else if unique_counters.contains(id) {
+                        CoverageStatus::Unique
+                    } else if is_best_input(*id) {
+                        CoverageStatus::Best
+                    } else {
+                        CoverageStatus::Hit
+                    };
+                }
             }
-            Json(block)
         }
     }
 }
 
-#[get("/best_input?<counter>")]
-fn best_input_for_counter(state: &State<ManagedData>, counter: usize) -> Json<String> {
+#[get("/best_input?<counter>&<target>")]
+async fn best_input_for_counter(server: &State<Server>, counter: usize, target: Option<&str>) -> ApiResult<String> {
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
     let pool_idx = state
-        .simplest_cov
-        .best_for_counter
-        .iter()
-        .find(|x| x.0 == counter)
-        .unwrap()
-        .1;
-    let name_input = &state.corpus_map.0.iter().find(|x| x.0 .1 == pool_idx).unwrap().1;
-    Json(name_input.clone())
+        .index
+        .best_input_for_counter
+        .get(&counter)
+        .ok_or(Error::UnknownCounter { id: counter })?;
+    Ok(Json(state.index.input_hash(*pool_idx)?.to_owned()))
 }
 
-#[get("/input?<hash>")]
-fn input(state: &State<ManagedData>, hash: &str) -> Json<String> {
-    let data = &state.all_inputs[hash];
-    let string = String::from_utf8_lossy(data).to_string();
+/// The input rendered as text by `decoder`, or by the decoder given on the
+/// command line
+#[get("/input?<hash>&<decoder>&<target>")]
+async fn input(
+    server: &State<Server>,
+    hash: &str,
+    decoder: form::Result<'_, InputDecoder>,
+    target: Option<&str>,
+) -> ApiResult<String> {
+    let decoder = optional_query_param("decoder", decoder)?;
+    Ok(Json(decode_input(server, hash, decoder, target).await?.text))
+}
+
+/// The input decoded by `decoder`, including its structure for JSON decoders
+#[get("/input/decoded?<hash>&<decoder>&<target>")]
+async fn decoded_input(
+    server: &State<Server>,
+    hash: &str,
+    decoder: form::Result<'_, InputDecoder>,
+    target: Option<&str>,
+) -> ApiResult<DecodedInput> {
+    let decoder = optional_query_param("decoder", decoder)?;
+    Ok(Json(decode_input(server, hash, decoder, target).await?))
+}
 
-    // let decoded: Vec<serde_json::Value> = serde_json::from_slice(&data).unwrap();
-    // let string: String = serde_json::from_value(decoded[1].clone()).unwrap();
+/// The original bytes of an input, to be downloaded
+#[derive(Responder)]
+struct RawInput {
+    content: (ContentType, Vec<u8>),
+    disposition: Header<'static>,
+}
+
+/// The content of the corpus file of the input, served as JSON or text if it
+/// can be read as such, and as binary data otherwise
+#[get("/input/raw?<hash>&<target>")]
+async fn raw_input(server: &State<Server>, hash: &str, target: Option<&str>) -> Result<RawInput, ApiError> {
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
+    let data = state
+        .all_inputs
+        .get(hash)
+        .ok_or_else(|| Error::UnknownInputHash { hash: hash.to_owned() })?;
+    let content_type = if state.binary_inputs.contains(hash) {
+        ContentType::Binary
+    } else if serde_json::from_slice::<serde::de::IgnoredAny>(data).is_ok() {
+        ContentType::JSON
+    } else {
+        ContentType::Plain
+    };
+    // the hash is the name of the corpus file, so it is safe to use as a file name
+    let disposition = Header::new("Content-Disposition", format!("attachment; filename=\"{}\"", hash));
+    Ok(RawInput {
+        content: (content_type, data.clone()),
+        disposition,
+    })
+}
 
-    Json(string)
+async fn decode_input(
+    server: &Server,
+    hash: &str,
+    decoder: Option<InputDecoder>,
+    target: Option<&str>,
+) -> Result<DecodedInput, ApiError> {
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
+    let data = state
+        .all_inputs
+        .get(hash)
+        .ok_or_else(|| Error::UnknownInputHash { hash: hash.to_owned() })?;
+    Ok(decoder.unwrap_or(server.args.decoder).decode(data)?)
 }
 
-#[get("/inputs")]
-fn inputs(state: &State<ManagedData>) -> Json<Vec<InputInfo>> {
+#[get("/inputs?<target>")]
+async fn inputs(server: &State<Server>, target: Option<&str>) -> ApiResult<Vec<InputInfo>> {
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
     let inputs = state
         .simplest_cov
         .ranked_inputs
         .iter()
         .map(|&pool_idx| {
-            let name_input = &state.corpus_map.0.iter().find(|x| x.0 .1 == pool_idx).unwrap().1;
-            InputInfo {
+            let hash = state.index.input_hash(pool_idx)?;
+            Ok(InputInfo {
                 pool_idx,
-                hash: name_input.clone(),
-            }
+                hash: hash.to_owned(),
+                binary: state.binary_inputs.contains(hash),
+            })
         })
-        .collect::<Vec<_>>();
-    Json(inputs)
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(Json(inputs))
+}
+
+#[get("/runs?<target>")]
+async fn runs(server: &State<Server>, target: Option<&str>) -> ApiResult<Vec<RunInfo>> {
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
+    let runs = stats_runs(&server.args.crate_directory, &state.test)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, run)| RunInfo {
+            index,
+            name: run_name(&run),
+            selected: run == state.stats_folder,
+        })
+        .collect();
+    Ok(Json(runs))
 }
 
-// allow html to reference any file with path /static under folder "static"
-#[get("/<file..>", rank = 10)] // use rank here to allow other api endpoint available as well
-async fn serve_static_file(file: PathBuf) -> Option<NamedFile> {
-    NamedFile::open(Path::new("../resources/").join(file)).await.ok()
+/// Replaces the data of the current run of the target with the one of the
+/// selected run
+#[post("/run?<run>&<target>")]
+async fn select_run(server: &State<Server>, run: &str, target: Option<&str>) -> ApiResult<RunInfo> {
+    let data = server.target(target).await?;
+    let args = server.args.with_test(&data.read().unwrap().test);
+    let run = RunSelector::from_string(run);
+    // the data of the target is only locked to swap in the loaded run
+    let (index, stats_folder, new_data) = blocking(move || {
+        let stats_folder = find_run(&args.crate_directory, &args.test, &run)?;
+        let index = stats_runs(&args.crate_directory, &args.test)?
+            .iter()
+            .position(|r| *r == stats_folder)
+            .unwrap_or_default();
+        let new_data = ManagedData::new(FuzzTestData::load_run(&args, &stats_folder)?)?;
+        Ok((index, stats_folder, new_data))
+    })
+    .await?;
+    *data.write().unwrap() = new_data;
+    Ok(Json(RunInfo {
+        index,
+        name: run_name(&stats_folder),
+        selected: true,
+    }))
 }
 
-#[launch]
-fn rocket() -> _ {
+/// Counters gained and lost since the `base` run of the `base_test` fuzz test,
+/// or of the target if `base_test` is not given
+#[get("/diff?<base>&<base_test>&<changed_only>&<target>")]
+async fn diff(
+    server: &State<Server>,
+    base: &str,
+    base_test: Option<&str>,
+    changed_only: Option<bool>,
+    target: Option<&str>,
+) -> ApiResult<Vec<FileDiff>> {
+    let data = server.target(target).await?;
+    let diff = coverage_diff(server, &data, base, base_test).await?;
+    let state = data.read().unwrap();
+    let mut summaries = diff.summaries(&state.coverage_map);
+    if changed_only.unwrap_or(false) {
+        for file in summaries.iter_mut() {
+            file.functions.retain(|function| !function.diff.is_empty());
+        }
+        summaries.retain(|file| !file.diff.is_empty());
+    }
+    Ok(Json(summaries))
+}
+
+/// Coverage of the lines changed by `git diff <range>` in the workspace, by
+/// default the uncommitted changes
+#[get("/patch?<range>&<input_filter>&<coverage_kind_filter>&<target>")]
+async fn patch(
+    server: &State<Server>,
+    range: Option<&str>,
+    input_filter: form::Result<'_, InputFilter>,
+    coverage_kind_filter: form::Result<'_, CoverageKindFilter>,
+    target: Option<&str>,
+) -> ApiResult<PatchCoverage> {
+    let input_filter = optional_query_param("input_filter", input_filter)?;
+    let coverage_kind_filter = optional_query_param("coverage_kind_filter", coverage_kind_filter)?;
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
+    let range = range.unwrap_or("HEAD");
+    let changed_lines = ChangedLines::from_git(&server.args.workspace_directory, range)?;
+    let counters = state.index.filtered_counters(
+        &input_filter.unwrap_or(InputFilter::All),
+        &coverage_kind_filter.unwrap_or(CoverageKindFilter::All),
+    )?;
+    Ok(Json(PatchCoverage::new(
+        range,
+        &changed_lines,
+        &state.coverage_map,
+        &counters,
+    )))
+}
+
+#[get("/diff/coverage?<base>&<base_test>&<function>&<target>")]
+async fn diff_coverage(
+    server: &State<Server>,
+    base: &str,
+    base_test: Option<&str>,
+    function: &str,
+    target: Option<&str>,
+) -> ApiResult<FunctionCoverage> {
+    let data = server.target(target).await?;
+    let diff = coverage_diff(server, &data, base, base_test).await?;
+    let state = data.read().unwrap();
+    let mut function_coverage = state
+        .function_coverage
+        .get(function)
+        .ok_or_else(|| Error::UnknownFunction {
+            name: function.to_owned(),
+        })?
+        .clone();
+    diff.apply(&mut function_coverage);
+    Ok(Json(function_coverage))
+}
+
+async fn coverage_diff(
+    server: &Server,
+    data: &RwLock<ManagedData>,
+    base: &str,
+    base_test: Option<&str>,
+) -> Result<CoverageDiff, ApiError> {
+    let test = match base_test {
+        Some(test) => test.to_owned(),
+        None => data.read().unwrap().test.clone(),
+    };
+    let stats_folder = find_run(&server.args.crate_directory, &test, &RunSelector::from_string(base))?;
+
+    // the base run is cached because the same diff is usually requested for many functions in a row
+    let cached = server.diff_base.lock().unwrap().clone();
+    let base = match cached {
+        Some(base) if base.stats_folder == stats_folder => base,
+        _ => {
+            // nothing is locked while loading, a concurrent request may load the same run
+            let workspace_directory = server.args.workspace_directory.clone();
+            let base = Arc::new(blocking(move || RunCoverage::load(&stats_folder, &workspace_directory)).await?);
+            *server.diff_base.lock().unwrap() = Some(base.clone());
+            base
+        }
+    };
+    let state = data.read().unwrap();
+    Ok(CoverageDiff::new(
+        &base.coverage_map,
+        &base.hit_counters,
+        &state.coverage_map,
+        &state.index.all_hit_counters,
+    ))
+}
+
+/// The fuzz tests of the crate
+#[get("/targets")]
+fn targets(server: &State<Server>) -> ApiResult<Vec<TargetInfo>> {
+    let default_target = server.default_target.read().unwrap().clone();
+    let loaded = server.targets.lock().unwrap();
+    let targets = fuzz_tests(&server.args.crate_directory)?
+        .into_iter()
+        .map(|name| TargetInfo {
+            loaded: loaded.contains_key(&name),
+            selected: name == default_target,
+            name,
+        })
+        .collect();
+    Ok(Json(targets))
+}
+
+/// Makes `target` the fuzz test used by the requests that don't specify one,
+/// loading its most recent run if needed
+#[post("/target?<target>")]
+async fn select_target(server: &State<Server>, target: &str) -> ApiResult<TargetInfo> {
+    server.target(Some(target)).await?;
+    *server.default_target.write().unwrap() = target.to_owned();
+    Ok(Json(TargetInfo {
+        name: target.to_owned(),
+        loaded: true,
+        selected: true,
+    }))
+}
+
+/// The source files modified since the run, whose coverage may be shown on
+/// the wrong code
+#[get("/sources/stale?<target>")]
+async fn stale_sources(server: &State<Server>, target: Option<&str>) -> ApiResult<Vec<PathBuf>> {
+    let data = server.target(target).await?;
+    let state = data.read().unwrap();
+    Ok(Json(state.sources.stale().to_vec()))
+}
+
+/// Stream of `reload` events, sent each time the data of `target` was
+/// reloaded because fuzzcheck modified it. Without `target`, the events are
+/// sent for the target used by the requests without one at that time.
+#[get("/events?<target>")]
+fn events<'a>(server: &'a State<Server>, target: Option<String>, mut shutdown: Shutdown) -> EventStream![Event + 'a] {
+    let mut reloads = server.reloads.subscribe();
+    EventStream! {
+        loop {
+            let reload = select! {
+                reload = reloads.recv() => match reload {
+                    Ok(reload) => reload,
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(_)) => continue,
+                },
+                _ = &mut shutdown => break,
+            };
+            let shown = match &target {
+                Some(target) => *target == reload.target,
+                None => *server.default_target.read().unwrap() == reload.target,
+            };
+            if shown {
+                yield Event::json(&reload).event("reload");
+            }
+        }
+    }
+}
+
+/// Reloads the data of each loaded target whenever fuzzcheck modifies the
+/// stats or the corpus of its run, and sends the reloaded run to `reloads`.
+///
+/// If the loaded run of a target is the most recent one, the watcher switches
+/// to the next run as soon as fuzzcheck creates it.
+fn watch(args: CliArguments, targets: Targets, reloads: broadcast::Sender<Reload>) {
+    let mut watched = HashMap::<String, WatchedTarget>::new();
+    loop {
+        std::thread::sleep(WATCH_INTERVAL);
+        // the lock is not held while reloading, so that other targets can be loaded meanwhile
+        let loaded = targets
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(test, data)| (test.clone(), data.clone()))
+            .collect::<Vec<_>>();
+        for (test, data) in loaded {
+            let args = args.with_test(&test);
+            let watched = watched.entry(test).or_insert_with(|| WatchedTarget {
+                latest: find_run(&args.crate_directory, &args.test, &RunSelector::Latest).ok(),
+                snapshot: None,
+            });
+            watch_target(&args, &data, watched, &reloads);
+        }
+    }
+}
+
+/// What the watcher knows about the files of a target since its last check
+struct WatchedTarget {
+    /// the most recent run of the target
+    latest: Option<PathBuf>,
+    /// the state of the files of the loaded run, `None` before the first check
+    snapshot: Option<Snapshot>,
+}
+
+fn watch_target(
+    args: &CliArguments,
+    data: &RwLock<ManagedData>,
+    watched: &mut WatchedTarget,
+    reloads: &broadcast::Sender<Reload>,
+) {
+    let CliArguments {
+        crate_directory, test, ..
+    } = args;
+    let loaded_run = data.read().unwrap().stats_folder.clone();
+    let mut stats_folder = loaded_run.clone();
+    if let Ok(new_latest) = find_run(crate_directory, test, &RunSelector::Latest) {
+        if watched.latest.as_ref() == Some(&stats_folder) {
+            stats_folder = new_latest.clone();
+        }
+        watched.latest = Some(new_latest);
+    }
+    let new_snapshot = Snapshot::take(crate_directory, test, &stats_folder);
+    if watched.snapshot.is_none() {
+        // the data was loaded before the watcher started
+        watched.snapshot = Some(new_snapshot);
+        return;
+    }
+    if watched.snapshot.as_ref() == Some(&new_snapshot) {
+        return;
+    }
+    // fuzzcheck may be in the middle of writing a file, in which case the
+    // data is reloaded again at the next iteration
+    let new_data = FuzzTestData::load_run(args, &stats_folder).and_then(ManagedData::new);
+    match new_data {
+        Ok(new_data) => {
+            let mut data = data.write().unwrap();
+            // another run may have been selected while the data was loading
+            if data.stats_folder != loaded_run {
+                watched.snapshot = None;
+                return;
+            }
+            *data = new_data;
+            drop(data);
+            watched.snapshot = Some(new_snapshot);
+            let index = stats_runs(crate_directory, test)
+                .ok()
+                .and_then(|runs| runs.iter().position(|r| *r == stats_folder))
+                .unwrap_or_default();
+            // there may be no browser listening to the events
+            let _ = reloads.send(Reload {
+                target: test.clone(),
+                run: RunInfo {
+                    index,
+                    name: run_name(&stats_folder),
+                    selected: true,
+                },
+            });
+        }
+        Err(e) => eprintln!("warning: can't reload {}: {}", stats_folder.display(), e),
+    }
+}
+
+/// One of the embedded assets. The rank lets the API routes match first.
+#[get("/<file..>", rank = 10)]
+fn serve_static_file(file: PathBuf) -> Option<(ContentType, &'static [u8])> {
+    let asset = ASSETS.iter().find(|asset| file == Path::new(asset.path))?;
+    Some((asset.content_type.clone(), asset.content))
+}
+
+#[rocket::main]
+async fn main() {
     let args = std::env::args().collect::<Vec<_>>();
 
     let parser = fuzzcheck_view::args::cli_argument_parser();
     let args = fuzzcheck_view::args::parse_arguments(&parser, &args);
 
-    let CliArguments {
-        crate_directory,
-        test: fuzz_test,
-        workspace_directory: source_folder,
-    } = args;
+    let data = FuzzTestData::load(&args).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        exit(1);
+    });
 
-    let fuzz_folder = crate_directory.join("fuzz").join(fuzz_test);
-    let stats_folder = fuzz_folder.join("stats");
-    let mut stats_folders = vec![];
-    for directory in std::fs::read_dir(stats_folder).unwrap() {
-        let directory = directory.unwrap();
-        if directory.file_type().unwrap().is_dir() {
-            stats_folders.push(directory.path());
+    match &args.command {
+        Command::Serve => {
+            let rocket = rocket(args, data).unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                exit(1);
+            });
+            if let Err(e) = rocket.launch().await {
+                eprintln!("error: {}", e);
+                exit(1);
+            }
         }
-    }
-    stats_folders.sort();
-    let stats_folder = stats_folders.last().unwrap();
-
-    println!("launching on {}", stats_folder.display());
-
-    let coverage_map: CoverageMap = {
-        let coverage_map_path = stats_folder.join("coverage_sensor.json");
-        println!("coverage map path: {}", coverage_map_path.display());
-        let coverage_map =
-            std::fs::read(&coverage_map_path).expect(&format!("can't read {}", coverage_map_path.display()));
-        let mut coverage_map: CoverageMap = serde_json::from_slice(&coverage_map).expect("can't parse coverage map");
-        coverage_map.map_relative_paths(|path| {
-            *path = source_folder.join(&path);
-        });
-        coverage_map
-    };
-    let simplest_cov: SerializedUniqCov = {
-        let simplest_cov_path = stats_folder.join("simplest_cov.json");
-        let simplest_cov =
-            std::fs::read(&simplest_cov_path).expect(&format!("can't read {}", simplest_cov_path.display()));
-        serde_json::from_slice(&simplest_cov).expect("can't parse simplest_cov")
-    };
-    let corpus_map: CorpusMap = {
-        let simplest_cov_path = stats_folder.join("world.json");
-        let simplest_cov =
-            std::fs::read(&simplest_cov_path).expect(&format!("can't read {}", simplest_cov_path.display()));
-        serde_json::from_slice(&simplest_cov).expect("can't parse world")
-    };
-    let all_inputs = read_input_corpus(&fuzz_folder.join("corpus"));
-
-    let mut cov_functions = coverage_map.functions();
-
-    for block in cov_functions.iter_mut() {
-        for line in block.lines.iter_mut() {
-            for span in line.spans.iter_mut() {
-                match &mut span.kind {
-                    CodeSpanKind::Untracked => {}
-                    CodeSpanKind::Inferred { inferred_from, status } => {
-                        *status = if simplest_cov
-                            .all_hit_counters
-                            .iter()
-                            .any(|hit_id| inferred_from.contains(hit_id))
-                        {
-                            CoverageStatus::Hit
-                        } else {
-                            CoverageStatus::NotHit
-                        }
-                    }
-                    CodeSpanKind::Tracked { id, status } => {
-                        *status = if simplest_cov.all_hit_counters.contains(&id) {
-                            CoverageStatus::Hit
-                        } else {
-                            CoverageStatus::NotHit
-                        };
-                    }
+        #[cfg(feature = "tui")]
+        Command::Tui => {
+            let functions = data.functions().unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                exit(1);
+            });
+            if let Err(e) = fuzzcheck_view::tui::run_tui(&data, &functions) {
+                eprintln!("error: {}", e);
+                exit(1);
+            }
+        }
+        #[cfg(not(feature = "tui"))]
+        Command::Tui => {
+            eprintln!("error: fuzzcheck-view was built without the tui feature");
+            exit(1);
+        }
+        Command::Summary { json, requirements } => {
+            let report = SummaryReport::new(&data.coverage_map, &data.index.all_hit_counters, requirements);
+            let mut out = std::io::stdout().lock();
+            let result = if *json {
+                serde_json::to_writer_pretty(&mut out, &report)
+                    .map_err(std::io::Error::from)
+                    .and_then(|_| writeln!(out))
+            } else {
+                report.write_table(&mut out)
+            };
+            if let Err(e) = result {
+                eprintln!("error: can't write the summary: {}", e);
+                exit(1);
+            }
+            // distinguishes unmet requirements from other errors
+            if !report.passed() {
+                exit(2);
+            }
+        }
+        Command::Patch {
+            range,
+            json,
+            min_coverage,
+        } => {
+            let changed_lines = ChangedLines::from_git(&args.workspace_directory, range).unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                exit(1);
+            });
+            let patch = PatchCoverage::new(range, &changed_lines, &data.coverage_map, &data.index.all_hit_counters);
+            let mut out = std::io::stdout().lock();
+            let result = if *json {
+                serde_json::to_writer_pretty(&mut out, &patch)
+                    .map_err(std::io::Error::from)
+                    .and_then(|_| writeln!(out))
+            } else {
+                patch.write_table(&mut out)
+            };
+            if let Err(e) = result {
+                eprintln!("error: can't write the patch coverage: {}", e);
+                exit(1);
+            }
+            if let Some(min_coverage) = min_coverage {
+                if patch.total.percentage < *min_coverage {
+                    eprintln!(
+                        "FAILED: the coverage of the changed lines is {:.2}%, below the minimum of {:.2}%",
+                        patch.total.percentage, min_coverage
+                    );
+                    exit(2);
+                }
+            }
+        }
+        Command::Snapshot => {
+            if let Err(e) = data
+                .sources
+                .save_snapshot(&args.workspace_directory, &data.stats_folder)
+            {
+                eprintln!("error: can't save the source files: {}", e);
+                exit(1);
+            }
+            println!(
+                "source files saved to {}",
+                data.stats_folder.join(SNAPSHOT_FOLDER).display()
+            );
+        }
+        Command::Export { html, lcov, cobertura } => {
+            let functions = data.functions().unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                exit(1);
+            });
+            if let Some(out_dir) = html {
+                if let Err(e) = fuzzcheck_view::html::export_html(&data, &functions, out_dir) {
+                    eprintln!("error: can't write HTML report to {}: {}", out_dir.display(), e);
+                    exit(1);
+                }
+                println!("HTML report written to {}", out_dir.display());
+            }
+            if let Some(path) = lcov {
+                let result = std::fs::File::create(path).and_then(|file| {
+                    let mut out = BufWriter::new(file);
+                    write_lcov(&data.coverage_map, &data.index.all_hit_counters, &args.test, &mut out)?;
+                    out.flush()
+                });
+                if let Err(e) = result {
+                    eprintln!("error: can't write LCOV tracefile to {}: {}", path.display(), e);
+                    exit(1);
                 }
+                println!("LCOV tracefile written to {}", path.display());
+            }
+            if let Some(path) = cobertura {
+                let result = std::fs::File::create(path).and_then(|file| {
+                    let mut out = BufWriter::new(file);
+                    write_cobertura(
+                        &data.coverage_map,
+                        &data.index.all_hit_counters,
+                        &args.workspace_directory,
+                        &mut out,
+                    )?;
+                    out.flush()
+                });
+                if let Err(e) = result {
+                    eprintln!("error: can't write Cobertura report to {}: {}", path.display(), e);
+                    exit(1);
+                }
+                println!("Cobertura report written to {}", path.display());
             }
         }
     }
+}
 
-    let mut functions_per_file = HashMap::<String, Vec<FunctionName>>::new();
-    let mut function_coverage = HashMap::<String, FunctionCoverage>::new();
-    for c in cov_functions {
-        let entry = functions_per_file.entry(c.file.clone()).or_default();
-        entry.push(c.name.clone());
-        let name = c.name.clone();
-        let old = function_coverage.insert(c.name.name.clone(), c);
-        // assert!(
-        //     old.is_none(),
-        //     "old: {:?}\nnew: {:?}",
-        //     old.unwrap(),
-        //     &function_coverage[&name.name]
-        // );
+fn rocket(args: CliArguments, data: FuzzTestData) -> Result<Rocket<Build>, Error> {
+    let default_target = data.test.clone();
+    let data = Arc::new(RwLock::new(ManagedData::new(data)?));
+    let targets = Arc::new(Mutex::new(HashMap::from([(default_target.clone(), data)])));
+    let (reloads, _) = broadcast::channel(16);
+    if args.watch {
+        let (args, targets, reloads) = (args.clone(), targets.clone(), reloads.clone());
+        std::thread::spawn(move || watch(args, targets, reloads));
     }
-    let data = ManagedData {
-        coverage_map,
-        simplest_cov,
-        functions_per_file,
-        function_coverage,
-        corpus_map,
-        all_inputs,
+    let server = Server {
+        args,
+        default_target: RwLock::new(default_target),
+        targets,
+        loading: Mutex::new(HashMap::new()),
+        diff_base: Mutex::new(None),
+        reloads,
     };
 
-    rocket::build().manage(data).mount(
-        "/",
-        routes![
-            index,
-            inputs,
-            functions,
-            input,
-            coverage,
-            best_input_for_counter,
-            serve_static_file
-        ],
-    )
+    // the command line takes precedence over Rocket.toml and the ROCKET_ variables
+    let mut config = rocket::Config::figment();
+    if let Some(address) = server.args.address {
+        config = config.merge(("address", address));
+    }
+    if let Some(port) = server.args.port {
+        config = config.merge(("port", port));
+    }
+    let base_path = server.args.base_path.clone();
+
+    let rocket = rocket::custom(config)
+        .manage(server)
+        .attach(AdHoc::on_liftoff("Viewer URL", |rocket| {
+            Box::pin(async move {
+                // the port is only known here if it was chosen by the system
+                let address = SocketAddr::new(rocket.config().address, rocket.config().port);
+                let base_path = &rocket.state::<Server>().unwrap().args.base_path;
+                println!("fuzzcheck-view is running at {}", viewer_url(address, base_path));
+            })
+        }))
+        .register(&base_path, catchers![default_catcher])
+        .mount(
+            &base_path,
+            routes![
+                index,
+                inputs,
+                functions,
+                generics,
+                summary,
+                search,
+                input,
+                decoded_input,
+                raw_input,
+                coverage,
+                file_coverage,
+                best_input_for_counter,
+                runs,
+                select_run,
+                targets,
+                select_target,
+                diff,
+                diff_coverage,
+                patch,
+                stale_sources,
+                events,
+                serve_static_file
+            ],
+        );
+    Ok(rocket)
+}
+
+/// fuzz test -> data of its loaded run, for the fuzz tests requested so far
+type Targets = Arc<Mutex<HashMap<String, Arc<RwLock<ManagedData>>>>>;
+
+/// Returns the URL of the webpage, which must end with a slash for the
+/// relative URLs of its requests to be under the base path.
+fn viewer_url(address: SocketAddr, base_path: &str) -> String {
+    if base_path == "/" {
+        format!("http://{}/", address)
+    } else {
+        format!("http://{}{}/", address, base_path)
+    }
+}
+
+struct Server {
+    args: CliArguments,
+    /// the fuzz test used by the requests that don't specify a target
+    default_target: RwLock<String>,
+    targets: Targets,
+    /// fuzz test -> lock held while it is loaded, so that it is loaded only once
+    loading: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    /// the last base run of a diff
+    diff_base: Mutex<Option<Arc<RunCoverage>>>,
+    /// notified each time the data of a target is reloaded by the watcher
+    reloads: broadcast::Sender<Reload>,
+}
+
+impl Server {
+    /// Returns the data of the fuzz test `target`, or of the default target if
+    /// it is not given. A fuzz test is loaded the first time it is requested,
+    /// with its most recent run.
+    async fn target(&self, target: Option<&str>) -> Result<Arc<RwLock<ManagedData>>, ApiError> {
+        let target = match target {
+            Some(target) => target.to_owned(),
+            None => self.default_target.read().unwrap().clone(),
+        };
+        if let Some(data) = self.targets.lock().unwrap().get(&target) {
+            return Ok(data.clone());
+        }
+        // only the discovered fuzz tests are loaded, as the target is part of a path
+        if !fuzz_tests(&self.args.crate_directory)?.contains(&target) {
+            return Err(Error::UnknownTarget { test: target }.into());
+        }
+        let loading = self.loading.lock().unwrap().entry(target.clone()).or_default().clone();
+        let (args, targets) = (self.args.with_test(&target), self.targets.clone());
+        // the targets are not locked while loading, so that the requests for the
+        // other targets and the watcher are not blocked
+        blocking(move || {
+            let _loading = loading.lock().unwrap();
+            // the target may have been loaded by another request meanwhile
+            if let Some(data) = targets.lock().unwrap().get(&target) {
+                return Ok(data.clone());
+            }
+            let data = FuzzTestData::load(&args)?;
+            let data = Arc::new(RwLock::new(ManagedData::new(data)?));
+            targets.lock().unwrap().insert(target, data.clone());
+            Ok(data)
+        })
+        .await
+    }
+}
+
+/// The payload of a `reload` event
+#[derive(Clone, Serialize)]
+struct Reload {
+    target: String,
+    #[serde(flatten)]
+    run: RunInfo,
 }
 
 struct ManagedData {
+    test: String,
+    stats_folder: PathBuf,
     coverage_map: CoverageMap,
-    functions_per_file: HashMap<String, Vec<FunctionName>>,
+    /// the distinct functions of each file, sorted by file and by line
+    functions_per_file: BTreeMap<String, Vec<FunctionName>>,
+    /// mangled name -> coverage of the function
     function_coverage: HashMap<String, FunctionCoverage>,
+    groups: Vec<FunctionGroup>,
+    /// mangled name -> index of the group of the function in `groups`
+    group_of: HashMap<String, usize>,
+    /// the functions of each file, with one name per group
+    merged_functions_per_file: BTreeMap<String, Vec<FunctionName>>,
+    /// mangled name of the representative of a group -> merged coverage of the group
+    merged_coverage: HashMap<String, FunctionCoverage>,
     simplest_cov: SerializedUniqCov,
-    corpus_map: CorpusMap,
     all_inputs: HashMap<String, Vec<u8>>,
+    index: RunIndex,
+    sources: SourceFiles,
+    /// names of the inputs of the corpus that are not text
+    binary_inputs: HashSet<String>,
+}
+
+impl ManagedData {
+    fn new(data: FuzzTestData) -> Result<Self, Error> {
+        let groups = group_functions(&data.coverage_map);
+        let coverage_of = |functions: &[&Function]| -> Result<FunctionCoverage, Error> {
+            let equivalents = EquivalentCounters::new(functions);
+            let mut coverage = functions[0].coverage(&data.sources)?;
+            coverage.set_hit_statuses(&equivalents.merge(&data.index.all_hit_counters));
+            Ok(coverage)
+        };
+
+        let mut functions_per_file = BTreeMap::<String, Vec<FunctionName>>::new();
+        let mut function_coverage = HashMap::<String, FunctionCoverage>::new();
+        let mut group_of = HashMap::<String, usize>::new();
+        let mut merged_functions_per_file = BTreeMap::<String, Vec<FunctionName>>::new();
+        let mut merged_coverage = HashMap::<String, FunctionCoverage>::new();
+        for (group_idx, group) in groups.iter().enumerate() {
+            for instantiation in group.instantiations.iter() {
+                // the coverage map may contain the same function several times
+                let functions = group.functions_named(&data.coverage_map, &instantiation.name);
+                function_coverage.insert(instantiation.name.clone(), coverage_of(&functions)?);
+                group_of.insert(instantiation.name.clone(), group_idx);
+                functions_per_file
+                    .entry(group.file.clone())
+                    .or_default()
+                    .push(instantiation.clone());
+            }
+            let mut merged = coverage_of(&group.functions(&data.coverage_map))?;
+            merged.name = group.merged_name();
+            merged_functions_per_file
+                .entry(group.file.clone())
+                .or_default()
+                .push(merged.name.clone());
+            merged_coverage.insert(group.representative.name.clone(), merged);
+        }
+        let binary_inputs = data
+            .all_inputs
+            .iter()
+            .filter(|(_, input)| is_binary(input))
+            .map(|(hash, _)| hash.clone())
+            .collect();
+        let FuzzTestData {
+            test,
+            stats_folder,
+            coverage_map,
+            simplest_cov,
+            all_inputs,
+            index,
+            sources,
+            ..
+        } = data;
+        Ok(ManagedData {
+            test,
+            stats_folder,
+            coverage_map,
+            simplest_cov,
+            functions_per_file,
+            function_coverage,
+            groups,
+            group_of,
+            merged_functions_per_file,
+            merged_coverage,
+            all_inputs,
+            index,
+            sources,
+            binary_inputs,
+        })
+    }
 }
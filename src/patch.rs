@@ -0,0 +1,356 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::fuzzcheck::{CoverageMap, Region};
+use crate::lcov::line_hits;
+
+/// The lines added or modified by a git diff, in each file.
+#[derive(Clone, Debug, Default)]
+pub struct ChangedLines {
+    /// path of the file, joined to the workspace folder like the paths of the
+    /// coverage map -> changed lines, in increasing order
+    pub files: BTreeMap<PathBuf, Vec<usize>>,
+}
+
+/// The coverage of the lines changed by a git diff.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PatchCoverage {
+    /// the revision range given to `git diff`
+    pub range: String,
+    pub total: PatchSummary,
+    /// the changed files containing instrumented code
+    pub files: Vec<FilePatchCoverage>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PatchSummary {
+    pub changed_lines: usize,
+    /// changed lines containing code instrumented by fuzzcheck
+    pub instrumented_lines: usize,
+    pub hit_lines: usize,
+    /// percentage of instrumented lines that were hit, which is 100 if there
+    /// are no instrumented lines
+    pub percentage: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FilePatchCoverage {
+    pub file: String,
+    pub summary: PatchSummary,
+    pub lines: Vec<ChangedLine>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChangedLine {
+    pub line: usize,
+    /// whether the line was hit, or `None` if it is not instrumented
+    pub hit: Option<bool>,
+}
+
+impl ChangedLines {
+    /// Returns the lines changed by `git diff <range>` in the workspace, e.g.
+    /// the uncommitted changes for `HEAD` or the changes of a branch for
+    /// `main...branch`.
+    pub fn from_git(workspace_directory: &Path, range: &str) -> Result<Self> {
+        let error = |message: String| Error::Git {
+            directory: workspace_directory.to_path_buf(),
+            message,
+        };
+        // --relative gives paths relative to the workspace, like the ones of the coverage map
+        let output = Command::new("git")
+            .args([
+                "diff",
+                "--unified=0",
+                "--no-color",
+                "--no-ext-diff",
+                "--relative",
+                "--no-prefix",
+            ])
+            .args(["--end-of-options", range, "--"])
+            .current_dir(workspace_directory)
+            .output()
+            .map_err(|e| error(e.to_string()))?;
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+            // the range is the only argument given by the user, so it is the
+            // cause of the failure if the workspace is in a git repository
+            let in_repository = Command::new("git")
+                .args(["rev-parse", "--git-dir"])
+                .current_dir(workspace_directory)
+                .output()
+                .is_ok_and(|output| output.status.success());
+            if in_repository {
+                return Err(Error::InvalidRange {
+                    range: range.to_owned(),
+                    message,
+                });
+            }
+            return Err(error(message));
+        }
+        Ok(Self::parse(
+            &String::from_utf8_lossy(&output.stdout),
+            workspace_directory,
+        ))
+    }
+
+    /// Reads the new side of the hunks of a diff with no context lines.
+    fn parse(diff: &str, workspace_directory: &Path) -> Self {
+        let mut files = BTreeMap::<PathBuf, Vec<usize>>::new();
+        let mut file = None;
+        // an added line starting with `++ ` looks like the header of the new
+        // side, which only comes before the first hunk of the file
+        let mut in_hunk = false;
+        for line in diff.lines() {
+            if line.starts_with("diff ") {
+                in_hunk = false;
+            } else if let Some(path) = line.strip_prefix("+++ ").filter(|_| !in_hunk) {
+                // deleted files have no new side
+                file = (path != "/dev/null").then(|| workspace_directory.join(path));
+            } else if let (Some(hunk), Some(file)) = (line.strip_prefix("@@ "), &file) {
+                in_hunk = true;
+                // @@ -<old start>[,<old count>] +<new start>[,<new count>] @@
+                let new_side = hunk.split(' ').find_map(|range| range.strip_prefix('+'));
+                let Some((start, count)) = new_side.and_then(parse_hunk_range) else {
+                    continue;
+                };
+                files.entry(file.clone()).or_default().extend(start..start + count);
+            }
+        }
+        ChangedLines { files }
+    }
+}
+
+fn parse_hunk_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+impl PatchCoverage {
+    /// Intersects the changed lines with the regions of the counters of the
+    /// coverage map. The hit status of a line is the one computed for LCOV
+    /// tracefiles.
+    pub fn new(
+        range: &str,
+        changed_lines: &ChangedLines,
+        coverage_map: &CoverageMap,
+        hit_counters: &HashSet<usize>,
+    ) -> Self {
+        let mut regions_per_file = BTreeMap::<&Path, Vec<(Region, bool)>>::new();
+        for function in coverage_map.functions.iter() {
+            let regions = regions_per_file.entry(&function.file).or_default();
+            for counter in function.common_counters() {
+                let hit = counter.id.is_hit(hit_counters);
+                regions.extend(counter.regions.into_iter().map(|region| (region, hit)));
+            }
+        }
+
+        let mut total = PatchSummary::default();
+        let mut files = vec![];
+        for (file, changed) in changed_lines.files.iter() {
+            let Some(regions) = regions_per_file.get(file.as_path()) else {
+                continue;
+            };
+            let hits = line_hits(regions.iter().map(|(region, hit)| (region, *hit)));
+            let lines = changed
+                .iter()
+                .map(|&line| ChangedLine {
+                    line,
+                    hit: hits.get(&line).copied(),
+                })
+                .collect::<Vec<_>>();
+            let summary = PatchSummary::new(&lines);
+            total.add(&summary);
+            files.push(FilePatchCoverage {
+                file: file.to_string_lossy().into_owned(),
+                summary,
+                lines,
+            });
+        }
+        PatchCoverage {
+            range: range.to_owned(),
+            total,
+            files,
+        }
+    }
+
+    /// Writes one row per changed file with instrumented code, listing the
+    /// changed lines that were not hit, followed by the total.
+    pub fn write_table(&self, out: &mut impl Write) -> io::Result<()> {
+        let name_width = self
+            .files
+            .iter()
+            .map(|file| file.file.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("File".len());
+        writeln!(
+            out,
+            "{:<name_width$}  {:>9}  {:>8}  Missed lines",
+            "File", "Lines", "Coverage"
+        )?;
+        for file in self.files.iter() {
+            let missed = file
+                .lines
+                .iter()
+                .filter(|line| line.hit == Some(false))
+                .map(|line| line.line)
+                .collect::<Vec<_>>();
+            write_row(out, &file.file, &file.summary, name_width)?;
+            writeln!(out, "  {}", line_ranges(&missed))?;
+        }
+        write_row(out, "TOTAL", &self.total, name_width)?;
+        writeln!(out)
+    }
+}
+
+fn write_row(out: &mut impl Write, name: &str, summary: &PatchSummary, name_width: usize) -> io::Result<()> {
+    write!(
+        out,
+        "{:<name_width$}  {:>9}  {:>7.2}%",
+        name,
+        format!("{}/{}", summary.hit_lines, summary.instrumented_lines),
+        summary.percentage
+    )
+}
+
+/// Formats increasing line numbers as ranges, e.g. `3-5, 8`.
+fn line_ranges(lines: &[usize]) -> String {
+    let mut ranges = Vec::<(usize, usize)>::new();
+    for &line in lines {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == line => *end = line,
+            _ => ranges.push((line, line)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl PatchSummary {
+    fn new(lines: &[ChangedLine]) -> Self {
+        let mut summary = PatchSummary {
+            changed_lines: lines.len(),
+            instrumented_lines: lines.iter().filter(|line| line.hit.is_some()).count(),
+            hit_lines: lines.iter().filter(|line| line.hit == Some(true)).count(),
+            percentage: 0.0,
+        };
+        summary.update_percentage();
+        summary
+    }
+
+    fn add(&mut self, other: &PatchSummary) {
+        self.changed_lines += other.changed_lines;
+        self.instrumented_lines += other.instrumented_lines;
+        self.hit_lines += other.hit_lines;
+        self.update_percentage();
+    }
+
+    fn update_percentage(&mut self) {
+        self.percentage = if self.instrumented_lines == 0 {
+            100.0
+        } else {
+            self.hit_lines as f64 / self.instrumented_lines as f64 * 100.0
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changed_lines(diff: &str) -> BTreeMap<PathBuf, Vec<usize>> {
+        ChangedLines::parse(diff, Path::new("ws")).files
+    }
+
+    #[test]
+    fn hunk_range_without_count_is_a_single_line() {
+        assert_eq!(parse_hunk_range("12"), Some((12, 1)));
+        assert_eq!(parse_hunk_range("12,3"), Some((12, 3)));
+        assert_eq!(parse_hunk_range("12,0"), Some((12, 0)));
+        assert_eq!(parse_hunk_range("a,3"), None);
+    }
+
+    #[test]
+    fn hunks_give_the_lines_of_the_new_side() {
+        let diff = "\
+diff --git src/lib.rs src/lib.rs
+index 1111111..2222222 100644
+--- src/lib.rs
++++ src/lib.rs
+@@ -3 +3 @@ fn a() {
+-    old();
++    new();
+@@ -10,0 +11,2 @@ fn b() {
++    c();
++    d();
+@@ -20,2 +22,0 @@ fn e() {
+-    f();
+-    g();
+";
+        assert_eq!(
+            changed_lines(diff),
+            BTreeMap::from([(PathBuf::from("ws/src/lib.rs"), vec![3, 11, 12])])
+        );
+    }
+
+    #[test]
+    fn deleted_files_have_no_changed_lines() {
+        let diff = "\
+diff --git src/old.rs src/old.rs
+deleted file mode 100644
+index 1111111..0000000
+--- src/old.rs
++++ /dev/null
+@@ -1,2 +0,0 @@
+-fn old() {
+-}
+diff --git src/new.rs src/new.rs
+new file mode 100644
+index 0000000..2222222
+--- /dev/null
++++ src/new.rs
+@@ -0,0 +1 @@
++fn new() {}
+";
+        assert_eq!(
+            changed_lines(diff),
+            BTreeMap::from([(PathBuf::from("ws/src/new.rs"), vec![1])])
+        );
+    }
+
+    #[test]
+    fn added_line_starting_with_plus_signs_is_not_a_header() {
+        let diff = "\
+diff --git src/lib.rs src/lib.rs
+index 1111111..2222222 100644
+--- src/lib.rs
++++ src/lib.rs
+@@ -1,0 +2,3 @@
++++ x;
++++ y;
++z();
+@@ -8,0 +11 @@
++w();
+";
+        assert_eq!(
+            changed_lines(diff),
+            BTreeMap::from([(PathBuf::from("ws/src/lib.rs"), vec![2, 3, 4, 11])])
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::fuzzcheck::CoverageMap;
+use crate::{CoverageSummary, FileSummary, FunctionName};
+
+/// The coverage of a whole run, checked against the requirements given to the
+/// `summary` command.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SummaryReport {
+    pub total: CoverageSummary,
+    pub files: Vec<FileSummary>,
+    /// the requirements that are not met, empty if the check passed
+    pub failures: Vec<String>,
+}
+
+/// What the coverage of a run must reach for the `summary` command to succeed.
+#[derive(Clone, Debug, Default)]
+pub struct Requirements {
+    /// minimum percentage of tracked counters that must be hit
+    pub min_coverage: Option<f64>,
+    /// functions that must be reached by at least one input
+    pub required_functions: Vec<String>,
+}
+
+impl SummaryReport {
+    pub fn new(coverage_map: &CoverageMap, hit_counters: &HashSet<usize>, requirements: &Requirements) -> Self {
+        let files = coverage_map.summaries(hit_counters);
+        let mut total = CoverageSummary::default();
+        for file in files.iter() {
+            total.add(&file.summary);
+        }
+
+        let mut failures = vec![];
+        if let Some(min_coverage) = requirements.min_coverage {
+            if total.percentage < min_coverage {
+                failures.push(format!(
+                    "the coverage is {:.2}%, below the minimum of {:.2}%",
+                    total.percentage, min_coverage
+                ));
+            }
+        }
+        for required in requirements.required_functions.iter() {
+            let mut matching = files
+                .iter()
+                .flat_map(|file| file.functions.iter())
+                .filter(|function| function_matches(&function.name, required))
+                .peekable();
+            if matching.peek().is_none() {
+                failures.push(format!("there is no function named {}", required));
+            } else if !matching.any(|function| function.summary.hit_counters > 0) {
+                failures.push(format!("the function {} is never hit", required));
+            }
+        }
+
+        SummaryReport { total, files, failures }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Writes the report as a table with one row per file, each followed by
+    /// the rows of its functions, and a last row with the total.
+    pub fn write_table(&self, out: &mut impl Write) -> io::Result<()> {
+        let name_width = self
+            .files
+            .iter()
+            .flat_map(|file| {
+                std::iter::once(file.file.chars().count())
+                    .chain(file.functions.iter().map(|f| f.name.demangled_name.chars().count() + 2))
+            })
+            .max()
+            .unwrap_or(0)
+            .max("Name".len());
+
+        writeln!(out, "{:<name_width$}  {:>9}  {:>8}", "Name", "Counters", "Coverage")?;
+        for file in self.files.iter() {
+            write_row(out, &file.file, &file.summary, name_width)?;
+            for function in file.functions.iter() {
+                let name = format!("  {}", function.name.demangled_name);
+                write_row(out, &name, &function.summary, name_width)?;
+            }
+        }
+        write_row(out, "TOTAL", &self.total, name_width)?;
+
+        for failure in self.failures.iter() {
+            writeln!(out, "FAILED: {}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_row(out: &mut impl Write, name: &str, summary: &CoverageSummary, name_width: usize) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:<name_width$}  {:>9}  {:>7.2}%",
+        name,
+        format!("{}/{}", summary.hit_counters, summary.tracked_counters),
+        summary.percentage
+    )
+}
+
+/// Returns true if `path` is the path of the function, without the hash added
+/// by the compiler, or a suffix of it made of whole path segments.
+fn function_matches(name: &FunctionName, path: &str) -> bool {
+    let full_path = name.path();
+    full_path == path || full_path.ends_with(&format!("::{}", path))
+}
@@ -0,0 +1,220 @@
+use regex::Regex;
+#[cfg(feature = "server")]
+use rocket::form::FromFormField;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{self, Error};
+use crate::{FileSummary, FunctionSummary};
+
+/// How the query of a search is matched against the names of the functions
+/// and their files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(FromFormField))]
+#[serde(rename_all = "kebab-case")]
+pub enum SearchMode {
+    /// the query appears in the name
+    #[default]
+    Substring,
+    /// the query matches the whole name, with `*` matching any sequence of
+    /// characters and `?` any single character
+    Glob,
+    /// the query is a regular expression found in the name
+    Regex,
+}
+
+/// A function matching a search, with its coverage.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub file: String,
+    pub function: FunctionSummary,
+    /// whether only the file of the function matched the query
+    pub file_match: bool,
+}
+
+/// A query against the demangled names of the functions and their files.
+pub struct FunctionSearch {
+    pattern: Regex,
+    /// match the paths of the functions, without the hash added by the compiler
+    strip_hash: bool,
+}
+
+/// How well a function matches a query, from the best to the worst match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Rank {
+    /// the match ends the name and starts at a segment of its path, e.g.
+    /// `parse_ident` in `parser::parse_ident`
+    PathSuffix,
+    /// the match starts at a segment of the path
+    SegmentStart,
+    /// the match is somewhere else in the name
+    Name,
+    /// only the file of the function matches
+    File,
+}
+
+impl FunctionSearch {
+    pub fn new(query: &str, mode: SearchMode, strip_hash: bool) -> error::Result<Self> {
+        let pattern = match mode {
+            SearchMode::Substring => regex::escape(query),
+            SearchMode::Glob => glob_to_regex(query),
+            SearchMode::Regex => query.to_owned(),
+        };
+        let pattern = Regex::new(&pattern).map_err(|error| Error::InvalidPattern {
+            pattern: query.to_owned(),
+            error,
+        })?;
+        Ok(FunctionSearch { pattern, strip_hash })
+    }
+
+    /// Returns the functions whose name or file matches the query, the best
+    /// matches first. Among equally good matches, shorter names come first.
+    pub fn search(&self, files: &[FileSummary]) -> Vec<SearchResult> {
+        let mut results = vec![];
+        for file in files {
+            let file_match = self.pattern.is_match(&file.file);
+            for function in file.functions.iter() {
+                let name = if self.strip_hash {
+                    function.name.path()
+                } else {
+                    function.name.demangled_name.clone()
+                };
+                let rank = match self.pattern.find(&name) {
+                    Some(m) => rank(&name, m.start(), m.end()),
+                    None if file_match => Rank::File,
+                    None => continue,
+                };
+                let result = SearchResult {
+                    file: file.file.clone(),
+                    function: function.clone(),
+                    file_match: rank == Rank::File,
+                };
+                results.push((rank, name, result));
+            }
+        }
+        results.sort_by(|(rank_x, name_x, _), (rank_y, name_y, _)| {
+            (rank_x, name_x.len(), name_x).cmp(&(rank_y, name_y.len(), name_y))
+        });
+        results.into_iter().map(|(_, _, result)| result).collect()
+    }
+}
+
+fn rank(name: &str, start: usize, end: usize) -> Rank {
+    let at_segment_start = start == 0 || name[..start].ends_with("::");
+    if !at_segment_start {
+        Rank::Name
+    } else if end == name.len() {
+        Rank::PathSuffix
+    } else {
+        Rank::SegmentStart
+    }
+}
+
+/// Translates a glob matching a whole name into a regular expression.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CoverageSummary, FunctionName};
+
+    fn function(path: &[&str]) -> FunctionSummary {
+        let mut name = String::from("_ZN");
+        for segment in path.iter().chain(["h0123456789abcdef"].iter()) {
+            name.push_str(&format!("{}{}", segment.len(), segment));
+        }
+        name.push('E');
+        FunctionSummary {
+            name: FunctionName {
+                demangled_name: rustc_demangle::demangle(&name).to_string(),
+                name,
+            },
+            summary: CoverageSummary::default(),
+        }
+    }
+
+    fn file(file: &str, functions: Vec<FunctionSummary>) -> FileSummary {
+        FileSummary {
+            file: file.to_owned(),
+            summary: CoverageSummary::default(),
+            functions,
+        }
+    }
+
+    fn search(query: &str, mode: SearchMode, files: &[FileSummary]) -> Vec<String> {
+        FunctionSearch::new(query, mode, true)
+            .unwrap()
+            .search(files)
+            .into_iter()
+            .map(|result| result.function.name.path())
+            .collect()
+    }
+
+    #[test]
+    fn glob_is_escaped_and_anchored() {
+        assert_eq!(glob_to_regex("parse_*"), "^parse_.*$");
+        assert_eq!(glob_to_regex("a?b"), "^a.b$");
+        assert_eq!(glob_to_regex("a.b+(c)"), r"^a\.b\+\(c\)$");
+    }
+
+    #[test]
+    fn glob_matches_whole_names() {
+        let files = [file(
+            "src/parser.rs",
+            vec![
+                function(&["parser", "parse_ident"]),
+                function(&["parser", "parse_idents"]),
+                function(&["my", "parser", "parse_ident"]),
+            ],
+        )];
+        assert_eq!(
+            search("parser::parse_ident", SearchMode::Glob, &files),
+            vec!["parser::parse_ident"]
+        );
+        assert_eq!(
+            search("parser::parse_ident?", SearchMode::Glob, &files),
+            vec!["parser::parse_idents"]
+        );
+        assert_eq!(
+            search("*parser::parse_ident", SearchMode::Glob, &files),
+            vec!["parser::parse_ident", "my::parser::parse_ident"]
+        );
+    }
+
+    #[test]
+    fn best_matches_come_first() {
+        let files = [
+            file(
+                "src/parser.rs",
+                vec![
+                    function(&["parser", "try_parse_ident"]),
+                    function(&["parser", "parse_ident_list"]),
+                    function(&["parser", "parse_ident"]),
+                    function(&["p", "parse_ident"]),
+                    function(&["parser", "parse_number"]),
+                ],
+            ),
+            file("src/parse_ident.rs", vec![function(&["lexer", "next"])]),
+        ];
+        assert_eq!(
+            search("parse_ident", SearchMode::Substring, &files),
+            vec![
+                "p::parse_ident",
+                "parser::parse_ident",
+                "parser::parse_ident_list",
+                "parser::try_parse_ident",
+                "lexer::next",
+            ]
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use crate::error::{Error, Result};
+use crate::highlight::Highlighting;
+
+/// folder of a run in which the snapshot command copies the source files
+pub const SNAPSHOT_FOLDER: &str = "sources";
+
+/// Where the source files of the instrumented code are read from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SourceOrigin {
+    /// the files of the workspace, as they are now
+    #[default]
+    WorkingTree,
+    /// the files as committed in a git revision
+    Revision(String),
+    /// the copies saved in the stats folder of the run by the snapshot command
+    Snapshot,
+}
+
+/// The content of the source files of the instrumented code, read once when
+/// a run is loaded.
+#[derive(Clone, Debug, Default)]
+pub struct SourceFiles {
+    files: HashMap<PathBuf, String>,
+    highlighting: HashMap<PathBuf, Highlighting>,
+    /// files of the working tree modified since the run, sorted by path
+    stale: Vec<PathBuf>,
+}
+
+impl SourceFiles {
+    pub(crate) fn new(files: HashMap<PathBuf, String>) -> Self {
+        let highlighting = files
+            .iter()
+            .map(|(path, content)| (path.clone(), Highlighting::new(content)))
+            .collect();
+        SourceFiles {
+            files,
+            highlighting,
+            stale: vec![],
+        }
+    }
+
+    /// Reads each of the given files once.
+    pub fn read<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Result<Self> {
+        let mut files = HashMap::new();
+        for path in paths {
+            if files.contains_key(path) {
+                continue;
+            }
+            let content = std::fs::read_to_string(path).map_err(|error| Error::SourceFile {
+                path: path.to_path_buf(),
+                error,
+            })?;
+            files.insert(path.to_path_buf(), content);
+        }
+        Ok(SourceFiles::new(files))
+    }
+
+    /// Reads each of the given files once from `origin`, and checks whether
+    /// the files of the working tree were modified since the run saved in
+    /// `stats_folder`.
+    pub fn load<'a>(
+        origin: &SourceOrigin,
+        paths: impl IntoIterator<Item = &'a Path>,
+        workspace_directory: &Path,
+        stats_folder: &Path,
+    ) -> Result<Self> {
+        let mut sources = match origin {
+            SourceOrigin::WorkingTree => SourceFiles::read(paths)?,
+            SourceOrigin::Revision(revision) => {
+                let mut files = HashMap::new();
+                for path in paths {
+                    if !files.contains_key(path) {
+                        files.insert(path.to_path_buf(), read_at_revision(path, revision)?);
+                    }
+                }
+                SourceFiles::new(files)
+            }
+            SourceOrigin::Snapshot => {
+                let mut files = HashMap::new();
+                for path in paths {
+                    if files.contains_key(path) {
+                        continue;
+                    }
+                    let snapshot = snapshot_path(stats_folder, workspace_directory, path);
+                    let content = std::fs::read_to_string(&snapshot).map_err(|error| Error::Read {
+                        path: snapshot,
+                        description: "snapshot of a source file",
+                        error,
+                    })?;
+                    files.insert(path.to_path_buf(), content);
+                }
+                SourceFiles::new(files)
+            }
+        };
+        // the files read from a revision or a snapshot can't have changed since the run
+        if *origin == SourceOrigin::WorkingTree {
+            sources.stale = sources.stale_files(workspace_directory, stats_folder);
+        }
+        Ok(sources)
+    }
+
+    /// Returns the content of a file that was read with the run.
+    pub fn get(&self, path: &Path) -> Result<&str> {
+        self.files
+            .get(path)
+            .map(String::as_str)
+            .ok_or_else(|| Error::SourceFile {
+                path: path.to_path_buf(),
+                error: io::Error::new(io::ErrorKind::NotFound, "the file was not loaded with the run"),
+            })
+    }
+
+    /// Returns the Rust tokens of a file that was read with the run.
+    pub fn highlighting(&self, path: &Path) -> Option<&Highlighting> {
+        self.highlighting.get(path)
+    }
+
+    /// Returns the files of the working tree that were modified since the run,
+    /// for which the coverage may be shown on the wrong text.
+    pub fn stale(&self) -> &[PathBuf] {
+        &self.stale
+    }
+
+    /// Copies the files into the stats folder of the run, so that they can be
+    /// read with [`SourceOrigin::Snapshot`] after the working tree changed.
+    pub fn save_snapshot(&self, workspace_directory: &Path, stats_folder: &Path) -> io::Result<()> {
+        for (path, content) in self.files.iter() {
+            let snapshot = snapshot_path(stats_folder, workspace_directory, path);
+            if let Some(parent) = snapshot.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(snapshot, content)?;
+        }
+        Ok(())
+    }
+
+    /// A file is stale if it differs from its snapshot or, if the run has no
+    /// snapshot, if it was modified after fuzzcheck wrote the coverage map.
+    fn stale_files(&self, workspace_directory: &Path, stats_folder: &Path) -> Vec<PathBuf> {
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+        let coverage_map_modified = modified(&stats_folder.join("coverage_sensor.json"));
+        let mut stale = self
+            .files
+            .iter()
+            .filter(|(path, content)| {
+                let snapshot = snapshot_path(stats_folder, workspace_directory, path);
+                match std::fs::read_to_string(snapshot) {
+                    Ok(snapshot) => snapshot != **content,
+                    Err(_) => match (modified(path), coverage_map_modified) {
+                        (Some(file), Some(coverage_map)) => file > coverage_map,
+                        _ => false,
+                    },
+                }
+            })
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        stale.sort();
+        stale
+    }
+}
+
+/// Returns the content of the file in the given git revision.
+fn read_at_revision(path: &Path, revision: &str) -> Result<String> {
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let error = |message: String| Error::Git {
+        directory: directory.to_path_buf(),
+        message,
+    };
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    // ./ makes the path relative to the folder of the file rather than to the root of the repository
+    let output = Command::new("git")
+        .args(["show", "--end-of-options", &format!("{}:./{}", revision, file_name)])
+        .current_dir(directory)
+        .output()
+        .map_err(|e| error(e.to_string()))?;
+    if !output.status.success() {
+        return Err(error(String::from_utf8_lossy(&output.stderr).trim().to_owned()));
+    }
+    String::from_utf8(output.stdout).map_err(|e| error(e.to_string()))
+}
+
+/// Returns the path of the copy of a source file in the snapshot of a run:
+/// its path relative to the workspace, or its absolute path if it is outside
+/// of the workspace, under the snapshot folder.
+fn snapshot_path(stats_folder: &Path, workspace_directory: &Path, path: &Path) -> PathBuf {
+    let relative = path.strip_prefix(workspace_directory).unwrap_or(path);
+    let mut snapshot = stats_folder.join(SNAPSHOT_FOLDER);
+    for component in relative.components() {
+        // the components that would escape the snapshot folder are dropped
+        if let Component::Normal(component) = component {
+            snapshot.push(component);
+        }
+    }
+    snapshot
+}
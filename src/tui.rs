@@ -0,0 +1,362 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::data::FuzzTestData;
+use crate::decode::InputDecoder;
+use crate::{CodeSpanKind, FunctionCoverage};
+
+/// maximum number of bytes of an input shown in its preview
+const PREVIEW_LENGTH: usize = 4000;
+/// number of lines scrolled by PageUp and PageDown
+const PAGE_LENGTH: u16 = 20;
+
+const HIT_COLOR: Color = Color::Rgb(135, 217, 108);
+const NOT_HIT_COLOR: Color = Color::Rgb(255, 102, 102);
+const LINENO_COLOR: Color = Color::DarkGray;
+
+const HELP: &str =
+    " q: quit | tab: next pane | ↑↓: move | a/s: previous/next file | k/l: previous/next function | enter: show coverage of input ";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Files,
+    Functions,
+    Inputs,
+    Source,
+}
+
+impl Pane {
+    const ALL: [Pane; 4] = [Pane::Files, Pane::Functions, Pane::Inputs, Pane::Source];
+
+    fn next(self, offset: usize) -> Pane {
+        let idx = Pane::ALL.iter().position(|&p| p == self).unwrap_or(0);
+        Pane::ALL[(idx + offset) % Pane::ALL.len()]
+    }
+}
+
+struct App<'a> {
+    data: &'a FuzzTestData,
+    functions: &'a [FunctionCoverage],
+    all_hit_counters: HashSet<usize>,
+    /// each file with the indices of its functions in `functions`
+    files: Vec<(&'a str, Vec<usize>)>,
+    /// pool index and name of the inputs, from the most to the least interesting
+    inputs: Vec<(usize, &'a str)>,
+    focus: Pane,
+    file_state: ListState,
+    function_state: ListState,
+    input_state: ListState,
+    source_scroll: u16,
+    /// the input whose coverage is shown, with the counters it hits
+    shown_input: Option<(usize, HashSet<usize>)>,
+}
+
+/// Shows the coverage of the given functions of the fuzz test in the terminal,
+/// until the user quits.
+///
+/// The status of the spans must have been computed beforehand, for example by
+/// [`FuzzTestData::functions`].
+pub fn run_tui(data: &FuzzTestData, functions: &[FunctionCoverage]) -> io::Result<()> {
+    let mut files = BTreeMap::<&str, Vec<usize>>::new();
+    for (function_idx, function) in functions.iter().enumerate() {
+        files.entry(function.file.as_str()).or_default().push(function_idx);
+    }
+    let inputs = data
+        .simplest_cov
+        .ranked_inputs
+        .iter()
+        .map(|&pool_idx| (pool_idx, data.input_hash(pool_idx).unwrap_or("unknown input")))
+        .collect();
+    let mut app = App {
+        data,
+        functions,
+        all_hit_counters: data.index.all_hit_counters.clone(),
+        files: files.into_iter().collect(),
+        inputs,
+        focus: Pane::Files,
+        file_state: ListState::default().with_selected(Some(0)),
+        function_state: ListState::default().with_selected(Some(0)),
+        input_state: ListState::default().with_selected(Some(0)),
+        source_scroll: 0,
+        shown_input: None,
+    };
+
+    let mut terminal = ratatui::try_init()?;
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Tab => self.focus = self.focus.next(1),
+                KeyCode::BackTab => self.focus = self.focus.next(Pane::ALL.len() - 1),
+                KeyCode::Char('a') => self.select_file(-1),
+                KeyCode::Char('s') => self.select_file(1),
+                KeyCode::Char('k') => self.select_function(-1),
+                KeyCode::Char('l') => self.select_function(1),
+                KeyCode::Up => self.move_in_focused_pane(-1),
+                KeyCode::Down => self.move_in_focused_pane(1),
+                KeyCode::PageUp => self.source_scroll = self.source_scroll.saturating_sub(PAGE_LENGTH),
+                KeyCode::PageDown => self.scroll_source(PAGE_LENGTH as isize),
+                KeyCode::Enter if self.focus == Pane::Inputs => self.toggle_shown_input(),
+                _ => {}
+            }
+        }
+    }
+
+    fn move_in_focused_pane(&mut self, offset: isize) {
+        match self.focus {
+            Pane::Files => self.select_file(offset),
+            Pane::Functions => self.select_function(offset),
+            Pane::Inputs => {
+                let selected = self.input_state.selected().unwrap_or(0);
+                self.input_state
+                    .select(Some(move_index(selected, offset, self.inputs.len())));
+            }
+            Pane::Source => self.scroll_source(offset),
+        }
+    }
+
+    fn select_file(&mut self, offset: isize) {
+        let selected = self.file_state.selected().unwrap_or(0);
+        self.file_state
+            .select(Some(move_index(selected, offset, self.files.len())));
+        self.function_state.select(Some(0));
+        self.source_scroll = 0;
+    }
+
+    fn select_function(&mut self, offset: isize) {
+        let selected = self.function_state.selected().unwrap_or(0);
+        let len = self.file_functions().len();
+        self.function_state.select(Some(move_index(selected, offset, len)));
+        self.source_scroll = 0;
+    }
+
+    fn scroll_source(&mut self, offset: isize) {
+        let len = self.selected_function().map_or(0, |f| f.lines.len());
+        self.source_scroll = move_index(self.source_scroll as usize, offset, len) as u16;
+    }
+
+    fn toggle_shown_input(&mut self) {
+        let Some(&(pool_idx, _)) = self.input_state.selected().and_then(|idx| self.inputs.get(idx)) else {
+            return;
+        };
+        if self.shown_input.as_ref().is_some_and(|(shown, _)| *shown == pool_idx) {
+            self.shown_input = None;
+        } else {
+            let counters = self
+                .data
+                .index
+                .counters_for_input
+                .get(&pool_idx)
+                .cloned()
+                .unwrap_or_default();
+            self.shown_input = Some((pool_idx, counters));
+        }
+    }
+
+    fn file_functions(&self) -> &[usize] {
+        self.file_state
+            .selected()
+            .and_then(|idx| self.files.get(idx))
+            .map_or(&[], |(_, functions)| functions.as_slice())
+    }
+
+    fn selected_function(&self) -> Option<&FunctionCoverage> {
+        let function_idx = self
+            .function_state
+            .selected()
+            .and_then(|idx| self.file_functions().get(idx))?;
+        self.functions.get(*function_idx)
+    }
+
+    /// Returns the counters whose status is shown: those hit by the selected
+    /// input, or by all inputs.
+    fn hit_counters(&self) -> &HashSet<usize> {
+        match &self.shown_input {
+            Some((_, counters)) => counters,
+            None => &self.all_hit_counters,
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [top, bottom, help] =
+            Layout::vertical([Constraint::Percentage(30), Constraint::Min(0), Constraint::Length(1)])
+                .areas(frame.area());
+        let [files_area, functions_area, inputs_area] = Layout::horizontal([
+            Constraint::Percentage(35),
+            Constraint::Percentage(40),
+            Constraint::Percentage(25),
+        ])
+        .areas(top);
+        let [source_area, preview_area] =
+            Layout::horizontal([Constraint::Percentage(70), Constraint::Percentage(30)]).areas(bottom);
+
+        self.draw_files(frame, files_area);
+        self.draw_functions(frame, functions_area);
+        self.draw_inputs(frame, inputs_area);
+        self.draw_source(frame, source_area);
+        self.draw_preview(frame, preview_area);
+        frame.render_widget(Paragraph::new(HELP).style(Style::new().fg(LINENO_COLOR)), help);
+    }
+
+    fn block(&self, title: String, pane: Pane) -> Block<'static> {
+        let block = Block::bordered().title(title);
+        if self.focus == pane {
+            block.border_style(Style::new().fg(Color::Cyan))
+        } else {
+            block
+        }
+    }
+
+    fn draw_files(&mut self, frame: &mut Frame, area: Rect) {
+        let hit_counters = self.hit_counters();
+        let items = self
+            .files
+            .iter()
+            .map(|(file, function_idxs)| {
+                let (hit, tracked) = function_idxs
+                    .iter()
+                    .map(|&idx| self.functions[idx].counters_hit(hit_counters))
+                    .fold((0, 0), |(h1, t1), (h2, t2)| (h1 + h2, t1 + t2));
+                ListItem::new(format!("{} ({}/{})", file, hit, tracked))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(self.block(format!("Files ({})", self.files.len()), Pane::Files))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.file_state);
+    }
+
+    fn draw_functions(&mut self, frame: &mut Frame, area: Rect) {
+        let hit_counters = self.hit_counters();
+        let items = self
+            .file_functions()
+            .iter()
+            .map(|&idx| {
+                let function = &self.functions[idx];
+                let (hit, tracked) = function.counters_hit(hit_counters);
+                ListItem::new(format!("{} ({}/{})", function.name.demangled_name, hit, tracked))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(self.block("Functions".to_owned(), Pane::Functions))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.function_state);
+    }
+
+    fn draw_inputs(&mut self, frame: &mut Frame, area: Rect) {
+        let shown = self.shown_input.as_ref().map(|(pool_idx, _)| *pool_idx);
+        let items = self
+            .inputs
+            .iter()
+            .map(|&(pool_idx, hash)| {
+                let marker = if shown == Some(pool_idx) { "* " } else { "  " };
+                ListItem::new(format!("{}{}", marker, hash))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(self.block(format!("Inputs ({})", self.inputs.len()), Pane::Inputs))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.input_state);
+    }
+
+    fn draw_source(&self, frame: &mut Frame, area: Rect) {
+        let title = match &self.shown_input {
+            Some((pool_idx, _)) => format!(
+                "Coverage of input {}",
+                self.data.input_hash(*pool_idx).unwrap_or("unknown input")
+            ),
+            None => "Coverage of all inputs".to_owned(),
+        };
+        let hit_counters = self.hit_counters();
+        let lines = self.selected_function().map_or_else(Vec::new, |function| {
+            let lineno_width = function.lines.last().map_or(1, |line| line.lineno.to_string().len());
+            function
+                .lines
+                .iter()
+                .map(|line| {
+                    let mut spans = vec![Span::styled(
+                        format!("{:>width$} ", line.lineno, width = lineno_width),
+                        Style::new().fg(LINENO_COLOR),
+                    )];
+                    spans.extend(line.spans.iter().map(|span| {
+                        let style = match &span.kind {
+                            CodeSpanKind::Untracked => Style::new(),
+                            CodeSpanKind::Inferred { inferred_from, status } => {
+                                let hit = match &self.shown_input {
+                                    Some(_) => inferred_from.iter().any(|id| hit_counters.contains(id)),
+                                    None => status.is_hit(),
+                                };
+                                Style::new().fg(status_color(hit))
+                            }
+                            CodeSpanKind::Tracked { id, status } => {
+                                let hit = match &self.shown_input {
+                                    Some(_) => hit_counters.contains(id),
+                                    None => status.is_hit(),
+                                };
+                                Style::new().fg(status_color(hit)).add_modifier(Modifier::UNDERLINED)
+                            }
+                        };
+                        Span::styled(span.text.replace('\t', "    "), style)
+                    }));
+                    Line::from(spans)
+                })
+                .collect()
+        });
+        let paragraph = Paragraph::new(lines)
+            .block(self.block(title, Pane::Source))
+            .scroll((self.source_scroll, 0));
+        frame.render_widget(paragraph, area);
+    }
+
+    fn draw_preview(&self, frame: &mut Frame, area: Rect) {
+        let selected = self.input_state.selected().and_then(|idx| self.inputs.get(idx));
+        let preview = selected
+            .and_then(|(_, hash)| self.data.all_inputs.get(*hash))
+            .map(|input| {
+                // the input is decoded whole, so that a text input is not cut inside a character
+                let text = match InputDecoder::Auto.decode(input) {
+                    Ok(decoded) => decoded.text,
+                    Err(_) => String::from_utf8_lossy(input).into_owned(),
+                };
+                text.chars().take(PREVIEW_LENGTH).collect::<String>()
+            })
+            .unwrap_or_default();
+        let paragraph = Paragraph::new(preview)
+            .block(Block::bordered().title("Preview"))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(paragraph, area);
+    }
+}
+
+fn status_color(hit: bool) -> Color {
+    if hit {
+        HIT_COLOR
+    } else {
+        NOT_HIT_COLOR
+    }
+}
+
+/// Moves `idx` by `offset`, staying within `0..len`.
+fn move_index(idx: usize, offset: isize, len: usize) -> usize {
+    idx.saturating_add_signed(offset).min(len.saturating_sub(1))
+}
@@ -0,0 +1,64 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The files of the stats folder that fuzzcheck rewrites during a fuzz session
+const STATS_FILES: [&str; 3] = ["coverage_sensor.json", "simplest_cov.json", "world.json"];
+
+/// The state of the files fuzzcheck writes while it is running, used to find
+/// out when the data of a run must be reloaded.
+///
+/// Two snapshots are equal if none of the watched files was created, removed
+/// or modified in between.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    stats_folder: PathBuf,
+    /// modification time of each of the `STATS_FILES`, if it exists
+    stats_files: Vec<Option<SystemTime>>,
+    /// number of inputs in the corpus, including the ones in its subfolders
+    corpus_len: usize,
+    /// most recent modification time of an input or a folder of the corpus
+    corpus_modified: Option<SystemTime>,
+}
+
+impl Snapshot {
+    /// Records the modification times of the stats files of the run saved in
+    /// `stats_folder` and of the corpus of the fuzz test.
+    pub fn take(crate_directory: &Path, test: &str, stats_folder: &Path) -> Self {
+        let stats_files = STATS_FILES
+            .iter()
+            .map(|file| modified(&stats_folder.join(file)))
+            .collect();
+        let corpus_folder = crate_directory.join("fuzz").join(test).join("corpus");
+        let mut corpus_len = 0;
+        let mut corpus_modified = None;
+        scan_corpus(&corpus_folder, &mut corpus_len, &mut corpus_modified);
+        Snapshot {
+            stats_folder: stats_folder.to_path_buf(),
+            stats_files,
+            corpus_len,
+            corpus_modified,
+        }
+    }
+}
+
+/// Counts the inputs of the corpus folder and of its subfolders, as they are
+/// read by [`read_input_corpus`](crate::fuzzcheck::read_input_corpus), and
+/// records their most recent modification time.
+fn scan_corpus(folder: &Path, len: &mut usize, last_modified: &mut Option<SystemTime>) {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        *last_modified = (*last_modified).max(modified(&path));
+        if path.is_dir() {
+            scan_corpus(&path, len, last_modified);
+        } else {
+            *len += 1;
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}